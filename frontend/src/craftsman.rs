@@ -0,0 +1,214 @@
+use gloo_net::http::Request;
+use serde::{Deserialize, Serialize};
+use wasm_bindgen_futures::spawn_local;
+use yew::prelude::*;
+use yew_router::prelude::*;
+
+use crate::Route;
+
+/// query of the detail route, links from a search pass the searched postcode along
+#[derive(Serialize, Deserialize, Clone, PartialEq, Default)]
+pub struct DetailQuery {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub postalcode: Option<String>,
+    /// ISO 3166-1 alpha-2 code of the postcode, Germany if missing
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub country: Option<String>,
+}
+
+impl DetailQuery {
+    /// parameters of `GET /craftsmen/{id}`, the country only matters along with a postcode
+    fn params(&self) -> Vec<(&'static str, String)> {
+        let Some(postalcode) = &self.postalcode else {
+            return Vec::new();
+        };
+        let mut params = vec![("postalcode", postalcode.clone())];
+        if let Some(country) = &self.country {
+            params.push(("country", country.clone()));
+        }
+        params
+    }
+}
+
+#[derive(Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct CraftsmanDetails {
+    pub id: i32,
+    pub name: String,
+    pub city: String,
+    pub street: String,
+    pub house_number: String,
+    /// in meters
+    pub max_driving_distance: f64,
+    pub profile_score: f64,
+    pub profile_picture_score: f64,
+    pub profile_description_score: f64,
+    pub review_score: Option<f64>,
+    /// km to the searched postcode
+    pub distance: Option<f64>,
+}
+
+#[derive(Deserialize, Clone, PartialEq)]
+pub struct Review {
+    id: i32,
+    rating: i16,
+    author: String,
+    text: String,
+    created_at: String,
+}
+
+#[derive(Deserialize, Clone, PartialEq)]
+pub struct Rating {
+    stars: Option<f64>,
+    count: i64,
+}
+
+#[derive(Deserialize, Clone, PartialEq)]
+pub struct Reviews {
+    rating: Option<Rating>,
+    reviews: Vec<Review>,
+}
+
+#[derive(Clone, PartialEq)]
+enum Loaded<T> {
+    Loading,
+    Done(T),
+    Failed(String),
+}
+
+pub async fn get_details(id: i32, query: &DetailQuery) -> Result<CraftsmanDetails, String> {
+    let resp = Request::get(&format!("/craftsmen/{id}"))
+        .query(query.params())
+        .send()
+        .await
+        .map_err(|err| err.to_string())?;
+    if !resp.ok() {
+        return Err(format!("{} ({})", resp.status(), resp.status_text()));
+    }
+    resp.json().await.map_err(|err| err.to_string())
+}
+
+async fn get_reviews(id: i32) -> Result<Reviews, String> {
+    let resp = Request::get(&format!("/craftsmen/{id}/reviews"))
+        .send()
+        .await
+        .map_err(|err| err.to_string())?;
+    if !resp.ok() {
+        return Err(format!("{} ({})", resp.status(), resp.status_text()));
+    }
+    resp.json().await.map_err(|err| err.to_string())
+}
+
+fn stars(rating: i16) -> String {
+    let rating = rating.clamp(0, 5) as usize;
+    format!("{}{}", "★".repeat(rating), "☆".repeat(5 - rating))
+}
+
+fn percent(score: f64) -> String {
+    format!("{:.0} %", score * 100.0)
+}
+
+#[derive(Properties, PartialEq)]
+pub struct DetailProps {
+    pub id: i32,
+}
+
+#[function_component(CraftsmanDetail)]
+pub fn craftsman_detail(props: &DetailProps) -> Html {
+    let location = use_location();
+    let query = location
+        .and_then(|location| location.query::<DetailQuery>().ok())
+        .unwrap_or_default();
+
+    let details = use_state(|| Loaded::Loading);
+    let reviews = use_state(|| Loaded::Loading);
+
+    {
+        let (details, reviews) = (details.clone(), reviews.clone());
+        use_effect_with((props.id, query), move |(id, query)| {
+            let (id, query) = (*id, query.clone());
+            details.set(Loaded::Loading);
+            reviews.set(Loaded::Loading);
+            spawn_local(async move {
+                details.set(match get_details(id, &query).await {
+                    Ok(loaded) => Loaded::Done(loaded),
+                    Err(err) => Loaded::Failed(err),
+                });
+                reviews.set(match get_reviews(id).await {
+                    Ok(loaded) => Loaded::Done(loaded),
+                    Err(err) => Loaded::Failed(err),
+                });
+            });
+        });
+    }
+
+    let details = match &*details {
+        Loaded::Loading => html! { <div>{"Lade Profil..."}</div> },
+        Loaded::Failed(err) => html! { <div>{"Profil konnte nicht geladen werden: "}{err}</div> },
+        Loaded::Done(details) => html! {
+            <>
+            <h1>{ &details.name }</h1>
+            <p>
+                { format!("{} {}", details.street, details.house_number) }<br />
+                { &details.city }
+            </p>
+            if let Some(distance) = details.distance {
+                <p>{ format!("{distance:.1} km entfernt") }</p>
+            }
+            <p>{ format!("Fährt bis zu {:.0} km", details.max_driving_distance / 1000.0) }</p>
+            <table>
+                <tbody>
+                    <tr><th>{"Profil"}</th><td>{ percent(details.profile_score) }</td></tr>
+                    <tr><th>{"Bild"}</th><td>{ percent(details.profile_picture_score) }</td></tr>
+                    <tr><th>{"Beschreibung"}</th><td>{ percent(details.profile_description_score) }</td></tr>
+                    <tr>
+                        <th>{"Bewertungen"}</th>
+                        <td>{ details.review_score.map_or_else(|| "-".to_owned(), percent) }</td>
+                    </tr>
+                </tbody>
+            </table>
+            </>
+        },
+    };
+
+    let reviews = match &*reviews {
+        Loaded::Loading => html! { <div>{"Lade Bewertungen..."}</div> },
+        Loaded::Failed(err) => {
+            html! { <div>{"Bewertungen konnten nicht geladen werden: "}{err}</div> }
+        }
+        Loaded::Done(Reviews { rating, reviews }) => html! {
+            <>
+            if let Some(Rating { stars: Some(average), count }) = rating {
+                <p>{ format!("{average:.1} von 5 Sternen ({count} Bewertungen)") }</p>
+            }
+            if reviews.is_empty() {
+                <p>{"Noch keine Bewertungen"}</p>
+            }
+            <ul>
+                {
+                    for reviews.iter().map(|review| html! {
+                        <li key={review.id}>
+                            <strong>{ stars(review.rating) }</strong>
+                            {" "}{ &review.author }{", "}
+                            // the date part of the timestamp
+                            { review.created_at.split('T').next().unwrap_or_default() }
+                            <p>{ &review.text }</p>
+                        </li>
+                    })
+                }
+            </ul>
+            </>
+        },
+    };
+
+    html! {
+        <>
+        <Link<Route> to={Route::Home}>{"Zurück zur Suche"}</Link<Route>>
+        {" | "}
+        <Link<Route> to={Route::EditCraftsman { id: props.id }}>{"Profil bearbeiten"}</Link<Route>>
+        { details }
+        <h2>{"Bewertungen"}</h2>
+        { reviews }
+        </>
+    }
+}
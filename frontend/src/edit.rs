@@ -0,0 +1,374 @@
+use gloo_net::http::Request;
+use serde::{Deserialize, Serialize};
+use wasm_bindgen_futures::spawn_local;
+use web_sys::{wasm_bindgen::JsCast, HtmlInputElement};
+use yew::prelude::*;
+use yew_router::prelude::*;
+
+use crate::craftsman::CraftsmanDetails;
+use crate::login::{bearer, store_token, stored_token, Login};
+use crate::media::{DescriptionForm, MediaProps, PictureForm};
+use crate::Route;
+
+/// the smallest radius that can be entered, as for the search filter
+const MIN_DRIVING_DISTANCE_KM: f64 = 1.0;
+/// the server's upper bound for the radius
+const MAX_DRIVING_DISTANCE_KM: f64 = 500.0;
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ProfileUpdate {
+    /// in meters
+    max_driving_distance: f64,
+}
+
+#[derive(Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Updated {
+    max_driving_distance: f64,
+}
+
+#[derive(Deserialize, Clone, PartialEq)]
+pub struct QueryResult {
+    id: i32,
+    updated: Updated,
+}
+
+#[derive(Deserialize, Clone, PartialEq)]
+pub struct FieldError {
+    pub field: String,
+    pub message: String,
+}
+
+#[derive(Deserialize)]
+pub struct ValidationErrors {
+    pub errors: Vec<FieldError>,
+}
+
+#[derive(Deserialize)]
+struct Preview {
+    postcodes: usize,
+}
+
+#[derive(Clone, PartialEq)]
+enum Status {
+    Idle,
+    Saving,
+    /// along with the ETag of the version just written
+    Saved(QueryResult, Option<String>),
+    Invalid(Vec<FieldError>),
+    /// the login code isn't valid anymore
+    Unauthorized,
+    /// someone else changed the profile since it was loaded
+    Conflict,
+    Failed(String),
+}
+
+/// the profile along with its ETag, which is passed back when saving
+async fn get_profile(id: i32) -> Result<(CraftsmanDetails, Option<String>), String> {
+    let resp = Request::get(&format!("/craftsmen/{id}"))
+        .send()
+        .await
+        .map_err(|err| err.to_string())?;
+    if !resp.ok() {
+        return Err(format!("{} ({})", resp.status(), resp.status_text()));
+    }
+    let etag = resp.headers().get("etag");
+    let details = resp.json().await.map_err(|err| err.to_string())?;
+    Ok((details, etag))
+}
+
+async fn get_preview(id: i32, max_driving_distance: f64) -> Option<usize> {
+    let resp = Request::get(&format!(
+        "/craftsmen/{id}/coverage/preview?maxDrivingDistance={max_driving_distance}"
+    ))
+    .send()
+    .await
+    .ok()?;
+    if !resp.ok() {
+        return None;
+    }
+    let preview: Preview = resp.json().await.ok()?;
+    Some(preview.postcodes)
+}
+
+async fn patch_profile(
+    id: i32,
+    token: &str,
+    etag: Option<String>,
+    update: &ProfileUpdate,
+) -> Status {
+    let request =
+        Request::patch(&format!("/craftsmen/{id}")).header("Authorization", &bearer(token));
+    let request = match etag {
+        Some(etag) => request.header("If-Match", &etag),
+        None => request,
+    };
+    let resp = match request.json(update) {
+        Ok(request) => request.send().await,
+        Err(err) => return Status::Failed(err.to_string()),
+    };
+
+    match resp {
+        Ok(resp) if resp.ok() => match resp.json().await {
+            Ok(result) => Status::Saved(result, resp.headers().get("etag")),
+            Err(err) => Status::Failed(err.to_string()),
+        },
+        Ok(resp) if resp.status() == 401 || resp.status() == 403 => Status::Unauthorized,
+        Ok(resp) if resp.status() == 412 => Status::Conflict,
+        Ok(resp) if resp.status() == 422 => match resp.json::<ValidationErrors>().await {
+            Ok(invalid) => Status::Invalid(invalid.errors),
+            Err(err) => Status::Failed(err.to_string()),
+        },
+        Ok(resp) => Status::Failed(format!("{} ({})", resp.status(), resp.status_text())),
+        Err(err) => Status::Failed(err.to_string()),
+    }
+}
+
+/// the radius in meters, checked before saving or previewing it so NaN or out of range values
+/// aren't sent
+fn parse_driving_distance(km: &str) -> Result<f64, FieldError> {
+    match km.trim().parse::<f64>() {
+        Ok(km) if (MIN_DRIVING_DISTANCE_KM..=MAX_DRIVING_DISTANCE_KM).contains(&km) => {
+            Ok(km * 1000.0)
+        }
+        _ => Err(FieldError {
+            field: "maxDrivingDistance".to_owned(),
+            message: format!(
+                "muss zwischen {MIN_DRIVING_DISTANCE_KM} und {MAX_DRIVING_DISTANCE_KM} km liegen"
+            ),
+        }),
+    }
+}
+
+fn input_value(event: InputEvent) -> String {
+    event
+        .target()
+        .map(|target| target.unchecked_into::<HtmlInputElement>().value())
+        .unwrap_or_default()
+}
+
+#[derive(Properties, PartialEq)]
+pub struct EditProps {
+    pub id: i32,
+}
+
+/// Lets craftsmen log in with the code they got for their profile, then change their radius
+/// and upload their picture and description.
+#[function_component(ProfileEdit)]
+pub fn profile_edit(props: &EditProps) -> Html {
+    let id = props.id;
+    let token = use_state(|| stored_token(id));
+    let expired = use_state(|| false);
+
+    {
+        let token = token.clone();
+        use_effect_with(id, move |id| token.set(stored_token(*id)));
+    }
+
+    let on_login = {
+        let (token, expired) = (token.clone(), expired.clone());
+        Callback::from(move |new_token: String| {
+            token.set(Some(new_token));
+            expired.set(false);
+        })
+    };
+
+    let logout = |was_expired: bool| {
+        let (token, expired) = (token.clone(), expired.clone());
+        move || {
+            store_token(id, None);
+            token.set(None);
+            expired.set(was_expired);
+        }
+    };
+    let on_unauthorized = {
+        let logout = logout(true);
+        Callback::from(move |_| logout())
+    };
+    let on_logout = {
+        let logout = logout(false);
+        Callback::from(move |_: MouseEvent| logout())
+    };
+
+    let content = match (*token).clone() {
+        None => html! { <Login {id} {on_login} expired={*expired} /> },
+        Some(token) => html! {
+            <>
+            <button onclick={on_logout}>{"Abmelden"}</button>
+            <RadiusForm {id} token={token.clone()} on_unauthorized={on_unauthorized.clone()} />
+            <h2>{"Beschreibung"}</h2>
+            <DescriptionForm
+                {id}
+                token={token.clone()}
+                on_unauthorized={on_unauthorized.clone()}
+            />
+            <h2>{"Profilbild"}</h2>
+            <PictureForm {id} {token} {on_unauthorized} />
+            </>
+        },
+    };
+
+    html! {
+        <>
+        <Link<Route> to={Route::Craftsman { id }}>{"Zurück zum Profil"}</Link<Route>>
+        <h1>{"Profil bearbeiten"}</h1>
+        { content }
+        </>
+    }
+}
+
+/// Changes the radius, previewing how many postcodes it covers while it is being entered.
+#[function_component(RadiusForm)]
+fn radius_form(props: &MediaProps) -> Html {
+    let id = props.id;
+    // in km, as it is entered
+    let distance = use_state(|| None::<String>);
+    let etag = use_state(|| None::<String>);
+    let load_error = use_state(|| None::<String>);
+    let preview = use_state(|| None::<usize>);
+    let status = use_state(|| Status::Idle);
+    // answers to outdated previews are dropped
+    let preview_request = use_mut_ref(|| 0_u32);
+
+    let request_preview = {
+        let (preview, preview_request) = (preview.clone(), preview_request.clone());
+        Callback::from(move |km: String| {
+            let Ok(max_driving_distance) = parse_driving_distance(&km) else {
+                preview.set(None);
+                return;
+            };
+
+            *preview_request.borrow_mut() += 1;
+            let request = *preview_request.borrow();
+            let (preview, preview_request) = (preview.clone(), preview_request.clone());
+            spawn_local(async move {
+                let postcodes = get_preview(id, max_driving_distance).await;
+                if *preview_request.borrow() == request {
+                    preview.set(postcodes);
+                }
+            });
+        })
+    };
+
+    {
+        let (distance, etag, load_error) = (distance.clone(), etag.clone(), load_error.clone());
+        let request_preview = request_preview.clone();
+        use_effect_with(id, move |id| {
+            let id = *id;
+            spawn_local(async move {
+                match get_profile(id).await {
+                    Ok((details, loaded_etag)) => {
+                        let km = (details.max_driving_distance / 1000.0).to_string();
+                        request_preview.emit(km.clone());
+                        distance.set(Some(km));
+                        etag.set(loaded_etag);
+                    }
+                    Err(err) => load_error.set(Some(err)),
+                }
+            });
+        });
+    }
+
+    let Some(current) = (*distance).clone() else {
+        return match &*load_error {
+            Some(err) => html! { <div>{"Profil konnte nicht geladen werden: "}{err}</div> },
+            None => html! { <div>{"Lade Profil..."}</div> },
+        };
+    };
+
+    let on_distance = {
+        let distance = distance.clone();
+        Callback::from(move |event: InputEvent| {
+            let value = input_value(event);
+            request_preview.emit(value.clone());
+            distance.set(Some(value));
+        })
+    };
+
+    let onsubmit = {
+        let (status, etag, current) = (status.clone(), etag.clone(), current.clone());
+        let (token, on_unauthorized) = (props.token.clone(), props.on_unauthorized.clone());
+        Callback::from(move |event: SubmitEvent| {
+            event.prevent_default();
+
+            let update = match parse_driving_distance(&current) {
+                Ok(max_driving_distance) => ProfileUpdate {
+                    max_driving_distance,
+                },
+                Err(error) => {
+                    status.set(Status::Invalid(vec![error]));
+                    return;
+                }
+            };
+
+            status.set(Status::Saving);
+            let (status, etag) = (status.clone(), etag.clone());
+            let (token, on_unauthorized) = (token.clone(), on_unauthorized.clone());
+            let current_etag = (*etag).clone();
+            spawn_local(async move {
+                let result = patch_profile(id, &token, current_etag, &update).await;
+                if result == Status::Unauthorized {
+                    on_unauthorized.emit(());
+                }
+                // the next change has to be based on the version just written, changes made
+                // by others since then still fail the precondition
+                if let Status::Saved(_, Some(new_etag)) = &result {
+                    etag.set(Some(new_etag.clone()));
+                }
+                status.set(result);
+            });
+        })
+    };
+
+    let field_error = |field: &str| match &*status {
+        Status::Invalid(errors) => errors
+            .iter()
+            .find(|error| error.field == field)
+            .map(|error| html! { <span>{" "}{ &error.message }</span> }),
+        _ => None,
+    };
+
+    let result = match &*status {
+        Status::Idle | Status::Invalid(_) | Status::Unauthorized => html! {},
+        Status::Saving => html! { <p>{"Speichere..."}</p> },
+        Status::Saved(QueryResult { updated, .. }, _) => html! {
+            <p>
+                { format!("Gespeichert: {:.1} km", updated.max_driving_distance / 1000.0) }
+            </p>
+        },
+        Status::Conflict => html! {
+            <p>{"Das Profil wurde inzwischen geändert, bitte lade die Seite neu."}</p>
+        },
+        Status::Failed(err) => html! { <p>{"Speichern fehlgeschlagen: "}{err}</p> },
+    };
+
+    html! {
+        <>
+        <form {onsubmit}>
+            <label>
+                {"Maximale Fahrstrecke (km) "}
+                <input
+                    type="number"
+                    min={MIN_DRIVING_DISTANCE_KM.to_string()}
+                    max={MAX_DRIVING_DISTANCE_KM.to_string()}
+                    step="any"
+                    value={current}
+                    oninput={on_distance}
+                />
+                { field_error("maxDrivingDistance") }
+            </label>
+            <p>
+                {
+                    match *preview {
+                        Some(postcodes) => format!("Damit erreichst du {postcodes} Postleitzahlen"),
+                        None => "-".to_owned(),
+                    }
+                }
+            </p>
+            <button type="submit" disabled={*status == Status::Saving}>{"Speichern"}</button>
+        </form>
+        { result }
+        </>
+    }
+}
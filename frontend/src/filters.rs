@@ -0,0 +1,121 @@
+use std::str::FromStr;
+
+use wasm_bindgen_futures::spawn_local;
+use web_sys::{wasm_bindgen::JsCast, HtmlInputElement, HtmlSelectElement};
+use yew::prelude::*;
+
+use crate::search::{get_services, SearchQuery, Service, Sort};
+
+/// value of the input or select the event came from
+fn target_value(event: &Event) -> String {
+    let Some(target) = event.target() else {
+        return String::new();
+    };
+    match target.dyn_into::<HtmlInputElement>() {
+        Ok(input) => input.value(),
+        Err(target) => target
+            .dyn_into::<HtmlSelectElement>()
+            .map(|select| select.value())
+            .unwrap_or_default(),
+    }
+}
+
+/// empty or malformed values clear the filter
+fn parse<T: FromStr>(value: &str) -> Option<T> {
+    value.trim().parse().ok()
+}
+
+#[derive(Properties, PartialEq)]
+pub struct FilterProps {
+    pub query: SearchQuery,
+    pub on_change: Callback<SearchQuery>,
+}
+
+/// Controls for the filters and the order of the search, every change is passed on as a new
+/// query.
+#[function_component(SearchFilters)]
+pub fn search_filters(props: &FilterProps) -> Html {
+    let services = use_state(Vec::<Service>::new);
+
+    {
+        let services = services.clone();
+        use_effect_with((), move |_| {
+            spawn_local(async move { services.set(get_services().await) });
+        });
+    }
+
+    let change = |update: fn(&mut SearchQuery, &str)| {
+        let (query, on_change) = (props.query.clone(), props.on_change.clone());
+        Callback::from(move |event: Event| {
+            let mut query = query.clone();
+            update(&mut query, &target_value(&event));
+            on_change.emit(query);
+        })
+    };
+
+    let on_max_distance = change(|query, value| query.max_distance = parse(value));
+    let on_min_score = change(|query, value| query.min_score = parse(value));
+    let on_sort = change(|query, value| {
+        query.sort = match value {
+            "distance" => Some(Sort::Distance),
+            _ => None,
+        }
+    });
+    let on_service = change(|query, value| query.service = parse(value));
+
+    let query = &props.query;
+    let by_distance = query.sort == Some(Sort::Distance);
+
+    html! {
+        <div>
+            <label>
+                {"Maximale Entfernung (km) "}
+                <input
+                    type="number"
+                    min="1"
+                    step="any"
+                    value={query.max_distance.map(|km| km.to_string()).unwrap_or_default()}
+                    onchange={on_max_distance}
+                />
+            </label>
+            {" "}
+            <label>
+                {"Mindestbewertung des Profils (0 bis 1) "}
+                <input
+                    type="number"
+                    min="0"
+                    max="1"
+                    step="0.05"
+                    value={query.min_score.map(|score| score.to_string()).unwrap_or_default()}
+                    onchange={on_min_score}
+                />
+            </label>
+            {" "}
+            <label>
+                {"Sortierung "}
+                <select onchange={on_sort}>
+                    <option value="rank" selected={!by_distance}>{"Ranking"}</option>
+                    <option value="distance" selected={by_distance}>{"Entfernung"}</option>
+                </select>
+            </label>
+            {" "}
+            <label>
+                {"Leistung "}
+                <select onchange={on_service}>
+                    <option value="" selected={query.service.is_none()}>{"Alle"}</option>
+                    {
+                        for services.iter().map(|service| html! {
+                            <option
+                                key={service.id}
+                                value={service.id.to_string()}
+                                selected={query.service == Some(service.id)}
+                            >
+                                { &service.name }
+                            </option>
+                        })
+                    }
+                </select>
+            </label>
+        </div>
+    }
+}
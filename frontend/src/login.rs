@@ -0,0 +1,117 @@
+use gloo_net::http::Request;
+use wasm_bindgen_futures::spawn_local;
+use web_sys::HtmlInputElement;
+use yew::prelude::*;
+
+/// key of the login code of a profile in the local storage
+fn storage_key(id: i32) -> String {
+    format!("craftsman-login-{id}")
+}
+
+fn storage() -> Option<web_sys::Storage> {
+    web_sys::window()?.local_storage().ok()?
+}
+
+/// the login code kept for the profile, if the craftsman logged in before
+pub fn stored_token(id: i32) -> Option<String> {
+    storage()?.get_item(&storage_key(id)).ok()?
+}
+
+/// keeps the code across visits, or forgets it if `None`
+pub fn store_token(id: i32, token: Option<&str>) {
+    let Some(storage) = storage() else {
+        return;
+    };
+    let _ = match token {
+        Some(token) => storage.set_item(&storage_key(id), token),
+        None => storage.remove_item(&storage_key(id)),
+    };
+}
+
+/// value of the `Authorization` header for requests on behalf of the craftsman
+pub fn bearer(token: &str) -> String {
+    format!("Bearer {token}")
+}
+
+/// Whether the code is the one of the profile, or why that couldn't be checked.
+async fn check_token(id: i32, token: &str) -> Result<bool, String> {
+    let resp = Request::post(&format!("/craftsmen/{id}/login"))
+        .header("Authorization", &bearer(token))
+        .send()
+        .await
+        .map_err(|err| err.to_string())?;
+
+    match resp.status() {
+        204 => Ok(true),
+        401 | 403 => Ok(false),
+        _ => Err(format!("{} ({})", resp.status(), resp.status_text())),
+    }
+}
+
+#[derive(Properties, PartialEq)]
+pub struct LoginProps {
+    pub id: i32,
+    /// with the checked code
+    pub on_login: Callback<String>,
+    /// e.g. after the code was replaced
+    #[prop_or_default]
+    pub expired: bool,
+}
+
+/// Asks for the login code the craftsman got for their profile.
+#[function_component(Login)]
+pub fn login(props: &LoginProps) -> Html {
+    let code = use_state(String::new);
+    let error = use_state(|| None::<String>);
+    let checking = use_state(|| false);
+
+    let oninput = {
+        let code = code.clone();
+        Callback::from(move |event: InputEvent| {
+            if let Some(input) = event.target_dyn_into::<HtmlInputElement>() {
+                code.set(input.value());
+            }
+        })
+    };
+
+    let onsubmit = {
+        let (code, error, checking) = (code.clone(), error.clone(), checking.clone());
+        let (id, on_login) = (props.id, props.on_login.clone());
+        Callback::from(move |event: SubmitEvent| {
+            event.prevent_default();
+
+            let token = code.trim().to_owned();
+            checking.set(true);
+            let (error, checking, on_login) = (error.clone(), checking.clone(), on_login.clone());
+            spawn_local(async move {
+                match check_token(id, &token).await {
+                    Ok(true) => {
+                        store_token(id, Some(&token));
+                        on_login.emit(token);
+                    }
+                    Ok(false) => error.set(Some("Der Code ist ungültig.".to_owned())),
+                    Err(err) => error.set(Some(format!("Anmeldung fehlgeschlagen: {err}"))),
+                }
+                checking.set(false);
+            });
+        })
+    };
+
+    html! {
+        <form {onsubmit}>
+            if props.expired {
+                <p>{"Deine Anmeldung ist abgelaufen, bitte melde dich erneut an."}</p>
+            }
+            <label>
+                {"Anmeldecode "}
+                <input type="password" value={(*code).clone()} {oninput} />
+            </label>
+            <button type="submit" disabled={*checking || code.trim().is_empty()}>
+                {"Anmelden"}
+            </button>
+            if let Some(error) = &*error {
+                <p>{ error }</p>
+            }
+        </form>
+    }
+}
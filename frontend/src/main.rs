@@ -1,16 +1,35 @@
-use std::ops::Deref;
-
 use gloo_net::http::Request;
 use serde::Deserialize;
 use wasm_bindgen_futures::{js_sys::JSON, spawn_local};
-use web_sys::{wasm_bindgen::JsCast, HtmlInputElement};
+use web_sys::{wasm_bindgen::JsCast, AbortController, HtmlInputElement};
 use yew::prelude::*;
 use yew_router::prelude::*;
 
+mod craftsman;
+mod edit;
+mod filters;
+mod login;
+mod map;
+mod media;
+mod search;
+
+use craftsman::{CraftsmanDetail, DetailQuery};
+use edit::ProfileEdit;
+use filters::SearchFilters;
+use map::MapView;
+use search::{
+    get_craftsmen, get_postcode_location, SearchAction, SearchQuery, SearchState, Sentinel, Status,
+};
+
 #[derive(Clone, Routable, PartialEq)]
 enum Route {
     #[at("/")]
     Home,
+    // not under /craftsmen, reloading those would get the JSON of the API
+    #[at("/profile/:id")]
+    Craftsman { id: i32 },
+    #[at("/profile/:id/edit")]
+    EditCraftsman { id: i32 },
     #[at("/hello-server")]
     HelloServer,
 }
@@ -23,6 +42,8 @@ fn switch(routes: Route) -> Html {
             html! {
             <CraftFinder /> }
         }
+        Route::Craftsman { id } => html! { <CraftsmanDetail {id} /> },
+        Route::EditCraftsman { id } => html! { <ProfileEdit {id} /> },
         Route::HelloServer => html! { <HelloServer /> },
     }
 }
@@ -45,76 +66,157 @@ pub struct Craftsman {
     street: String,
     house_number: String,
     distance: f64,
+    lat: f64,
+    lon: f64,
 }
 
-#[derive(Deserialize)]
-pub struct APIResponse {
-    craftsmen: Vec<Craftsman>,
-}
+#[function_component(CraftFinder)]
+fn craftfinder() -> Html {
+    // the search is taken from the query string, so it can be shared and navigated back to
+    let query = use_location()
+        .and_then(|location| location.query::<SearchQuery>().ok())
+        .unwrap_or_default();
+    let navigator = use_navigator();
 
-struct Data {
-    pub offset: u64,
-    pub postcode: String,
-    pub data: Vec<Craftsman>,
+    // pages are loaded as the end of the list is scrolled into view
+    let search = use_reducer(SearchState::default);
+    let selected = use_state(|| None::<i32>);
+    // request of the current search, a new search aborts it
+    let in_flight = use_mut_ref(|| None::<AbortController>);
 
-}
+    let load = {
+        let (search, in_flight) = (search.dispatcher(), in_flight.clone());
+        Callback::from(move |(query, offset): (SearchQuery, usize)| {
+            let Ok(controller) = AbortController::new() else {
+                search.dispatch(SearchAction::Failed("Anfrage nicht möglich".to_owned()));
+                return;
+            };
+            if let Some(previous) = in_flight.borrow_mut().replace(controller.clone()) {
+                previous.abort();
+            }
+
+            let search = search.clone();
+            spawn_local(async move {
+                let signal = controller.signal();
+                let center = match (&query.postalcode, offset) {
+                    (Some(postcode), 0) => {
+                        get_postcode_location(postcode, query.country.as_deref(), &signal).await
+                    }
+                    _ => None,
+                };
+                let result = get_craftsmen(&query, offset, &signal).await;
+                // the response may have arrived just before another search started
+                if signal.aborted() {
+                    return;
+                }
 
-impl Data {
-    pub fn init() -> Self {
-        Self { offset: 0, postcode: "".to_owned(), data: Vec::new() }
+                search.dispatch(match result {
+                    Ok(craftsmen) => SearchAction::Loaded { craftsmen, center },
+                    Err(err) => SearchAction::Failed(err),
+                });
+            });
+        })
+    };
+
+    // every change of the query string starts over, including back and forward navigation
+    {
+        let (search, load) = (search.dispatcher(), load.clone());
+        use_effect_with(query.clone(), move |query| {
+            if let Some(previous) = in_flight.borrow_mut().take() {
+                previous.abort();
+            }
+            search.dispatch(SearchAction::Search(query.clone()));
+            if query.postalcode.is_some() {
+                load.emit((query.clone(), 0));
+            }
+        });
     }
 
-    
-}
-#[function_component(CraftFinder)]
-fn craftfinder() -> Html {
-    // first get 20 into list and trigger loading more with button
+    let navigate = Callback::from(move |query: SearchQuery| {
+        if let Some(navigator) = &navigator {
+            // only fails for queries that can't be serialized, which ours always can
+            let _ = navigator.push_with_query(&Route::Home, &query);
+        }
+    });
 
-    let state = use_state(|| Data::init());
+    let postcode_changes = {
+        let (query, navigate) = (query.clone(), navigate.clone());
+        Callback::from(move |postcode: String| {
+            let postcode = postcode.trim().to_owned();
+            navigate.emit(SearchQuery {
+                postalcode: (!postcode.is_empty()).then_some(postcode),
+                ..query.clone()
+            });
+        })
+    };
 
-    let data = state.clone();
-    let postcode_changes = Callback::from(move |postcode : String| {
-        let data = data.clone();
-        spawn_local(async move {
-            let mut result = get_craftsmen(postcode.clone(), data.offset).await;
-            let (offset,new)  = if postcode == data.postcode {
-                let mut vals = data.data.clone();
-                vals.append(&mut result);
-                (vals.len() as u64, vals)
+    let load_more = {
+        let (search, load) = (search.clone(), load.clone());
+        Callback::from(move |_| {
+            if matches!(search.status, Status::Ready | Status::Failed(_)) {
+                search.dispatch(SearchAction::LoadMore);
+                load.emit((search.query.clone(), search.offset()));
             }
-            else {
-                (0, result)
-            };
+        })
+    };
 
-            data.set(Data { offset, postcode, data: new });
-        });
-    });
+    let retry = load_more.reform(|_: MouseEvent| ());
 
     let form_onsubmit = Callback::from(|_: Vec<Craftsman>| {});
 
-    let data = state.clone();
+    let data = search.clone();
     let onsubmit = Callback::from(move |event: SubmitEvent| {
         event.prevent_default();
-        let data = data.deref().clone();
-        form_onsubmit.emit(data.data.clone());
+        form_onsubmit.emit(data.craftsmen.clone());
     });
 
     let onchange = Callback::from(move |event: Event| {
-        let value = event
-            .target()
-            .unwrap()
-            .unchecked_into::<HtmlInputElement>()
-            .value();
-        postcode_changes.emit(value);
+        if let Some(target) = event.target() {
+            postcode_changes.emit(target.unchecked_into::<HtmlInputElement>().value());
+        }
     });
 
-    let data = state.clone();
+    let data = search.clone();
+
+    let on_select = {
+        let selected = selected.clone();
+        Callback::from(move |id: i32| selected.set(Some(id)))
+    };
+
+    let status = match &data.status {
+        Status::Idle => html! {},
+        Status::Loading => html! { <p>{"Lade Handwerker..."}</p> },
+        Status::Ready => html! { <Sentinel on_visible={load_more} /> },
+        Status::Failed(err) => html! {
+            <p>
+                {"Laden fehlgeschlagen: "}{err}{" "}
+                <button onclick={retry}>{"Erneut versuchen"}</button>
+            </p>
+        },
+        Status::Exhausted if data.craftsmen.is_empty() => {
+            html! { <p>{"Keine Handwerker gefunden"}</p> }
+        }
+        Status::Exhausted => html! { <p>{"Keine weiteren Handwerker"}</p> },
+    };
 
     html! {
         <><form onsubmit={onsubmit}>
-        <input type="text" name={"PLZ"} onchange={onchange} placeholder={""} />
+        <input
+            type="text"
+            name={"PLZ"}
+            value={query.postalcode.clone().unwrap_or_default()}
+            onchange={onchange}
+            placeholder={""}
+        />
         <button type="submit"> {"Suche"}</button>
         </form>
+        <SearchFilters query={query.clone()} on_change={navigate} />
+        <MapView
+            center={data.center}
+            craftsmen={data.craftsmen.clone()}
+            selected={*selected}
+            on_select={on_select.clone()}
+        />
         <table>
             <thead>
                 <tr>
@@ -124,10 +226,29 @@ fn craftfinder() -> Html {
             </thead>
             <tbody>
                 {
-                    for data.data.clone().iter().map(|item| {
+                    for data.craftsmen.iter().map(|item| {
+                        let onclick = {
+                            let (id, on_select) = (item.id, on_select.clone());
+                            Callback::from(move |_: MouseEvent| on_select.emit(id))
+                        };
+                        let style = if Some(item.id) == *selected {
+                            "cursor: pointer; background-color: #ffedd5;"
+                        } else {
+                            "cursor: pointer;"
+                        };
                         html! {
-                            <tr>
-                                <td>{ &item.name }</td>
+                            <tr key={item.id} {onclick} {style}>
+                                <td>
+                                    <Link<Route, DetailQuery>
+                                        to={Route::Craftsman { id: item.id }}
+                                        query={Some(DetailQuery {
+                                            postalcode: Some(data.postcode().to_owned()),
+                                            country: data.query.country.clone(),
+                                        })}
+                                    >
+                                        { &item.name }
+                                    </Link<Route, DetailQuery>>
+                                </td>
                                 <td>{ &item.ranking_score }</td>
                             </tr>
                         }
@@ -135,25 +256,11 @@ fn craftfinder() -> Html {
                 }
             </tbody>
         </table>
+        { status }
         </>
     }
 }
 
-async fn get_craftsmen(postcode: String, offset: u64) -> Vec<Craftsman> {
-    // do one request starting from offset
-    let resp = Request::get(format!("/craftsmen?postalcode={postcode}&offset={offset}").as_str())
-        .send()
-        .await
-        .unwrap();
-    let result = if resp.ok() {
-        let response: APIResponse = serde_json::from_value(resp.json().await.unwrap()).unwrap();
-        response.craftsmen
-    } else {
-        Vec::new()
-    };
-    result
-}
-
 #[function_component(HelloServer)]
 fn hello_server() -> Html {
     let data = use_state(|| None);
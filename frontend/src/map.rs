@@ -0,0 +1,192 @@
+use js_sys::{Array, Object, Reflect};
+use wasm_bindgen::prelude::*;
+use web_sys::HtmlElement;
+use yew::prelude::*;
+
+use crate::Craftsman;
+
+/// stand-in tiles rendered by the server, point this at a real tile server for production
+const TILE_URL: &str = "/tiles/{z}/{x}/{y}";
+const TILE_ATTRIBUTION: &str = "Kachel-Platzhalter";
+
+const ZOOM: f64 = 11.0;
+
+// Leaflet is loaded as a global script in index.html
+#[wasm_bindgen]
+extern "C" {
+    type Map;
+
+    #[wasm_bindgen(js_namespace = L, js_name = map)]
+    fn leaflet_map(element: &HtmlElement) -> Map;
+
+    #[wasm_bindgen(method, js_name = setView)]
+    fn set_view(this: &Map, center: &JsValue, zoom: f64) -> Map;
+
+    #[wasm_bindgen(method, js_name = panTo)]
+    fn pan_to(this: &Map, center: &JsValue) -> Map;
+
+    #[wasm_bindgen(method)]
+    fn remove(this: &Map) -> Map;
+
+    type Layer;
+
+    #[wasm_bindgen(js_namespace = L, js_name = tileLayer)]
+    fn tile_layer(url: &str, options: &JsValue) -> Layer;
+
+    #[wasm_bindgen(js_namespace = L, js_name = layerGroup)]
+    fn layer_group() -> Layer;
+
+    #[wasm_bindgen(js_namespace = L, js_name = circleMarker)]
+    fn circle_marker(center: &JsValue, options: &JsValue) -> Layer;
+
+    /// adds the layer to a map or a layer group
+    #[wasm_bindgen(method, js_name = addTo)]
+    fn add_to(this: &Layer, target: &JsValue) -> Layer;
+
+    #[wasm_bindgen(method, js_name = clearLayers)]
+    fn clear_layers(this: &Layer) -> Layer;
+
+    #[wasm_bindgen(method, js_name = bindTooltip)]
+    fn bind_tooltip(this: &Layer, content: &str) -> Layer;
+
+    #[wasm_bindgen(method)]
+    fn on(this: &Layer, event: &str, handler: &Closure<dyn FnMut()>) -> Layer;
+}
+
+fn lat_lng((lat, lon): (f64, f64)) -> JsValue {
+    Array::of2(&lat.into(), &lon.into()).into()
+}
+
+fn options(entries: &[(&str, JsValue)]) -> JsValue {
+    let options = Object::new();
+    for (key, value) in entries {
+        // can't fail on a plain object
+        let _ = Reflect::set(&options, &(*key).into(), value);
+    }
+    options.into()
+}
+
+struct Leaflet {
+    map: Map,
+    markers: Layer,
+    /// click handlers of the markers, they have to live as long as the markers do
+    handlers: Vec<Closure<dyn FnMut()>>,
+}
+
+#[derive(Properties, PartialEq)]
+pub struct MapProps {
+    /// location of the searched postcode
+    pub center: Option<(f64, f64)>,
+    pub craftsmen: Vec<Craftsman>,
+    pub selected: Option<i32>,
+    pub on_select: Callback<i32>,
+}
+
+/// The searched postcode and the loaded craftsmen on a map. Clicking a craftsman selects them.
+#[function_component(MapView)]
+pub fn map_view(props: &MapProps) -> Html {
+    let container = use_node_ref();
+    let leaflet = use_mut_ref(|| None::<Leaflet>);
+
+    {
+        let container = container.clone();
+        let leaflet = leaflet.clone();
+        use_effect_with((), move |_| {
+            if let Some(element) = container.cast::<HtmlElement>() {
+                let map = leaflet_map(&element);
+                tile_layer(
+                    TILE_URL,
+                    &options(&[("attribution", TILE_ATTRIBUTION.into())]),
+                )
+                .add_to(&map);
+                let markers = layer_group().add_to(&map);
+
+                *leaflet.borrow_mut() = Some(Leaflet {
+                    map,
+                    markers,
+                    handlers: Vec::new(),
+                });
+            }
+
+            move || {
+                if let Some(leaflet) = leaflet.borrow_mut().take() {
+                    leaflet.map.remove();
+                }
+            }
+        });
+    }
+
+    {
+        let leaflet = leaflet.clone();
+        use_effect_with(props.center, move |center| {
+            if let (Some(leaflet), Some(center)) = (leaflet.borrow().as_ref(), center) {
+                leaflet.map.set_view(&lat_lng(*center), ZOOM);
+            }
+        });
+    }
+
+    {
+        let leaflet = leaflet.clone();
+        use_effect_with(
+            (
+                props.center,
+                props.craftsmen.clone(),
+                props.selected,
+                props.on_select.clone(),
+            ),
+            move |(center, craftsmen, selected, on_select)| {
+                if let Some(leaflet) = leaflet.borrow_mut().as_mut() {
+                    leaflet.markers.clear_layers();
+                    leaflet.handlers.clear();
+
+                    if let Some(center) = center {
+                        circle_marker(
+                            &lat_lng(*center),
+                            &options(&[("radius", 6.into()), ("color", "#dc2626".into())]),
+                        )
+                        .bind_tooltip("Gesuchte PLZ")
+                        .add_to(&leaflet.markers);
+                    }
+
+                    for craftsman in craftsmen {
+                        let (radius, color) = if Some(craftsman.id) == *selected {
+                            (10, "#ea580c")
+                        } else {
+                            (6, "#2563eb")
+                        };
+
+                        let (id, on_select) = (craftsman.id, on_select.clone());
+                        let handler = Closure::<dyn FnMut()>::new(move || on_select.emit(id));
+
+                        circle_marker(
+                            &lat_lng((craftsman.lat, craftsman.lon)),
+                            &options(&[("radius", radius.into()), ("color", color.into())]),
+                        )
+                        .bind_tooltip(&craftsman.name)
+                        .on("click", &handler)
+                        .add_to(&leaflet.markers);
+
+                        leaflet.handlers.push(handler);
+                    }
+                }
+            },
+        );
+    }
+
+    {
+        let craftsmen = props.craftsmen.clone();
+        use_effect_with(props.selected, move |selected| {
+            let selected = craftsmen
+                .iter()
+                .find(|craftsman| Some(craftsman.id) == *selected);
+
+            if let (Some(leaflet), Some(craftsman)) = (leaflet.borrow().as_ref(), selected) {
+                leaflet.map.pan_to(&lat_lng((craftsman.lat, craftsman.lon)));
+            }
+        });
+    }
+
+    html! {
+        <div ref={container} style="height: 24rem; width: 100%;"></div>
+    }
+}
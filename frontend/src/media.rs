@@ -0,0 +1,247 @@
+use gloo_net::http::Request;
+use serde::{Deserialize, Serialize};
+use wasm_bindgen_futures::spawn_local;
+use web_sys::{HtmlInputElement, HtmlTextAreaElement};
+use yew::prelude::*;
+
+use crate::edit::{FieldError, ValidationErrors};
+use crate::login::bearer;
+
+/// the server's upper bound for descriptions
+const MAX_DESCRIPTION_LENGTH: usize = 2000;
+
+#[derive(Serialize, Deserialize)]
+struct Description {
+    description: String,
+}
+
+#[derive(Clone, PartialEq)]
+enum Upload {
+    Idle,
+    Saving,
+    Saved,
+    Invalid(Vec<FieldError>),
+    /// the login code isn't valid anymore
+    Unauthorized,
+    Failed(String),
+}
+
+/// the description so far, empty if the craftsman hasn't written one yet
+async fn get_description(id: i32) -> Result<String, String> {
+    let resp = Request::get(&format!("/craftsmen/{id}/description"))
+        .send()
+        .await
+        .map_err(|err| err.to_string())?;
+    match resp.status() {
+        404 => Ok(String::new()),
+        _ if resp.ok() => resp
+            .json::<Description>()
+            .await
+            .map(|description| description.description)
+            .map_err(|err| err.to_string()),
+        _ => Err(format!("{} ({})", resp.status(), resp.status_text())),
+    }
+}
+
+/// result of an upload from its response
+async fn uploaded(resp: Result<gloo_net::http::Response, gloo_net::Error>) -> Upload {
+    match resp {
+        Ok(resp) if resp.ok() => Upload::Saved,
+        Ok(resp) if resp.status() == 401 || resp.status() == 403 => Upload::Unauthorized,
+        Ok(resp) if resp.status() == 415 => {
+            Upload::Failed("nur JPEG, PNG und WebP sind möglich".to_owned())
+        }
+        Ok(resp) if resp.status() == 413 => Upload::Failed("die Datei ist zu groß".to_owned()),
+        Ok(resp) if resp.status() == 422 => match resp.json::<ValidationErrors>().await {
+            Ok(invalid) => Upload::Invalid(invalid.errors),
+            Err(err) => Upload::Failed(err.to_string()),
+        },
+        Ok(resp) => Upload::Failed(format!("{} ({})", resp.status(), resp.status_text())),
+        Err(err) => Upload::Failed(err.to_string()),
+    }
+}
+
+async fn put_description(id: i32, token: &str, description: String) -> Upload {
+    let request = Request::put(&format!("/craftsmen/{id}/description"))
+        .header("Authorization", &bearer(token))
+        .json(&Description { description });
+    match request {
+        Ok(request) => uploaded(request.send().await).await,
+        Err(err) => Upload::Failed(err.to_string()),
+    }
+}
+
+async fn put_picture(id: i32, token: &str, picture: web_sys::File) -> Upload {
+    let request = Request::put(&format!("/craftsmen/{id}/picture"))
+        .header("Authorization", &bearer(token))
+        .header("Content-Type", &picture.type_())
+        .body(picture);
+    match request {
+        Ok(request) => uploaded(request.send().await).await,
+        Err(err) => Upload::Failed(err.to_string()),
+    }
+}
+
+fn upload_status(upload: &Upload) -> Html {
+    match upload {
+        Upload::Idle | Upload::Invalid(_) | Upload::Unauthorized => html! {},
+        Upload::Saving => html! { <p>{"Speichere..."}</p> },
+        Upload::Saved => html! { <p>{"Gespeichert"}</p> },
+        Upload::Failed(err) => html! { <p>{"Speichern fehlgeschlagen: "}{err}</p> },
+    }
+}
+
+#[derive(Properties, PartialEq)]
+pub struct MediaProps {
+    pub id: i32,
+    pub token: String,
+    /// the code was rejected, the craftsman has to log in again
+    pub on_unauthorized: Callback<()>,
+}
+
+/// Lets the craftsman write the description shown on their profile.
+#[function_component(DescriptionForm)]
+pub fn description_form(props: &MediaProps) -> Html {
+    let id = props.id;
+    let description = use_state(|| None::<String>);
+    let status = use_state(|| Upload::Idle);
+
+    {
+        let (description, status) = (description.clone(), status.clone());
+        use_effect_with(id, move |id| {
+            let id = *id;
+            spawn_local(async move {
+                match get_description(id).await {
+                    Ok(loaded) => description.set(Some(loaded)),
+                    Err(err) => status.set(Upload::Failed(err)),
+                }
+            });
+        });
+    }
+
+    let Some(current) = (*description).clone() else {
+        return html! { <p>{"Lade Beschreibung..."}</p> };
+    };
+
+    let oninput = {
+        let description = description.clone();
+        Callback::from(move |event: InputEvent| {
+            if let Some(textarea) = event.target_dyn_into::<HtmlTextAreaElement>() {
+                description.set(Some(textarea.value()));
+            }
+        })
+    };
+
+    let onsubmit = {
+        let (status, token, on_unauthorized) = (
+            status.clone(),
+            props.token.clone(),
+            props.on_unauthorized.clone(),
+        );
+        let current = current.clone();
+        Callback::from(move |event: SubmitEvent| {
+            event.prevent_default();
+
+            if current.chars().count() > MAX_DESCRIPTION_LENGTH {
+                status.set(Upload::Invalid(vec![FieldError {
+                    field: "description".to_owned(),
+                    message: format!("darf höchstens {MAX_DESCRIPTION_LENGTH} Zeichen lang sein"),
+                }]));
+                return;
+            }
+
+            status.set(Upload::Saving);
+            let (status, token, on_unauthorized) =
+                (status.clone(), token.clone(), on_unauthorized.clone());
+            let description = current.clone();
+            spawn_local(async move {
+                let result = put_description(id, &token, description).await;
+                if result == Upload::Unauthorized {
+                    on_unauthorized.emit(());
+                }
+                status.set(result);
+            });
+        })
+    };
+
+    let error = match &*status {
+        Upload::Invalid(errors) => errors
+            .iter()
+            .find(|error| error.field == "description")
+            .map(|error| html! { <span>{" "}{ &error.message }</span> }),
+        _ => None,
+    };
+
+    html! {
+        <form {onsubmit}>
+            <label>
+                {"Beschreibung"}
+                <br />
+                <textarea rows="6" cols="60" value={current} {oninput} />
+                { error }
+            </label>
+            <br />
+            <button type="submit" disabled={*status == Upload::Saving}>
+                {"Beschreibung speichern"}
+            </button>
+            { upload_status(&status) }
+        </form>
+    }
+}
+
+/// Lets the craftsman upload the picture shown on their profile.
+#[function_component(PictureForm)]
+pub fn picture_form(props: &MediaProps) -> Html {
+    let id = props.id;
+    let status = use_state(|| Upload::Idle);
+    // changed after each upload, so the browser doesn't show the cached picture
+    let revision = use_state(|| 0_u32);
+
+    let onchange = {
+        let (status, revision) = (status.clone(), revision.clone());
+        let (token, on_unauthorized) = (props.token.clone(), props.on_unauthorized.clone());
+        Callback::from(move |event: Event| {
+            let Some(picture) = event
+                .target_dyn_into::<HtmlInputElement>()
+                .and_then(|input| input.files())
+                .and_then(|files| files.get(0))
+            else {
+                return;
+            };
+
+            status.set(Upload::Saving);
+            let (status, revision) = (status.clone(), revision.clone());
+            let (token, on_unauthorized) = (token.clone(), on_unauthorized.clone());
+            spawn_local(async move {
+                let result = put_picture(id, &token, picture).await;
+                match result {
+                    Upload::Saved => revision.set(*revision + 1),
+                    Upload::Unauthorized => on_unauthorized.emit(()),
+                    _ => {}
+                }
+                status.set(result);
+            });
+        })
+    };
+
+    html! {
+        <div>
+            <img
+                src={format!("/craftsmen/{id}/picture?revision={}", *revision)}
+                alt="Noch kein Profilbild"
+                style="max-width: 200px; max-height: 200px;"
+            />
+            <br />
+            <label>
+                {"Profilbild (JPEG, PNG oder WebP, bis 2 MB) "}
+                <input
+                    type="file"
+                    accept="image/jpeg,image/png,image/webp"
+                    disabled={*status == Upload::Saving}
+                    {onchange}
+                />
+            </label>
+            { upload_status(&status) }
+        </div>
+    }
+}
@@ -0,0 +1,270 @@
+use std::rc::Rc;
+
+use gloo_net::http::Request;
+use js_sys::Array;
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+use web_sys::{AbortSignal, Element, IntersectionObserver, IntersectionObserverEntry};
+use yew::prelude::*;
+
+use crate::Craftsman;
+
+/// craftsmen per response, the server's `LIMIT`
+const PAGE_SIZE: usize = 20;
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Sort {
+    Rank,
+    Distance,
+}
+
+/// Query string of the search page, so searches can be shared and navigated back to. Missing
+/// filters are left out of the URL.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Default)]
+pub struct SearchQuery {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub postalcode: Option<String>,
+    /// ISO 3166-1 alpha-2 code of the postcode, Germany if missing
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub country: Option<String>,
+    /// in km
+    #[serde(rename = "maxDistance", skip_serializing_if = "Option::is_none")]
+    pub max_distance: Option<f64>,
+    /// minimum profile score between 0 and 1
+    #[serde(rename = "minScore", skip_serializing_if = "Option::is_none")]
+    pub min_score: Option<f64>,
+    /// by rank if missing
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sort: Option<Sort>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub service: Option<i32>,
+}
+
+impl SearchQuery {
+    /// parameters of `GET /craftsmen`, which takes the filters under the same names
+    fn params(&self, offset: usize) -> Vec<(&'static str, String)> {
+        let mut params = vec![("offset", offset.to_string())];
+        if let Some(postalcode) = &self.postalcode {
+            params.push(("postalcode", postalcode.clone()));
+        }
+        if let Some(country) = &self.country {
+            params.push(("country", country.clone()));
+        }
+        if let Some(max_distance) = self.max_distance {
+            params.push(("maxDistance", max_distance.to_string()));
+        }
+        if let Some(min_score) = self.min_score {
+            params.push(("minScore", min_score.to_string()));
+        }
+        if let Some(Sort::Distance) = self.sort {
+            params.push(("sort", "distance".to_owned()));
+        }
+        if let Some(service) = self.service {
+            params.push(("service", service.to_string()));
+        }
+        params
+    }
+}
+
+#[derive(Deserialize, Clone, PartialEq)]
+pub struct Service {
+    pub id: i32,
+    pub name: String,
+}
+
+#[derive(Deserialize)]
+struct ServicesResponse {
+    services: Vec<Service>,
+}
+
+#[derive(Deserialize)]
+struct APIResponse {
+    craftsmen: Vec<Craftsman>,
+}
+
+#[derive(Deserialize)]
+struct PostcodeLocation {
+    lat: f64,
+    lon: f64,
+}
+
+#[derive(Clone, PartialEq, Default)]
+pub enum Status {
+    /// nothing searched yet
+    #[default]
+    Idle,
+    Loading,
+    /// more craftsmen can be loaded
+    Ready,
+    Failed(String),
+    /// the last page was shorter than a full one
+    Exhausted,
+}
+
+#[derive(Clone, PartialEq, Default)]
+pub struct SearchState {
+    pub query: SearchQuery,
+    pub craftsmen: Vec<Craftsman>,
+    pub center: Option<(f64, f64)>,
+    pub status: Status,
+}
+
+pub enum SearchAction {
+    /// starts over with another postcode or other filters
+    Search(SearchQuery),
+    LoadMore,
+    Loaded {
+        craftsmen: Vec<Craftsman>,
+        /// only looked up along with the first page
+        center: Option<(f64, f64)>,
+    },
+    Failed(String),
+}
+
+impl Reducible for SearchState {
+    type Action = SearchAction;
+
+    fn reduce(self: Rc<Self>, action: Self::Action) -> Rc<Self> {
+        let state = match action {
+            SearchAction::Search(query) => SearchState {
+                status: match query.postalcode {
+                    Some(_) => Status::Loading,
+                    None => Status::Idle,
+                },
+                query,
+                ..Default::default()
+            },
+            SearchAction::LoadMore => SearchState {
+                status: Status::Loading,
+                ..(*self).clone()
+            },
+            SearchAction::Loaded { craftsmen, center } => {
+                let status = if craftsmen.len() < PAGE_SIZE {
+                    Status::Exhausted
+                } else {
+                    Status::Ready
+                };
+                let mut state = (*self).clone();
+                state.craftsmen.extend(craftsmen);
+                state.center = center.or(state.center);
+                state.status = status;
+                state
+            }
+            SearchAction::Failed(err) => SearchState {
+                status: Status::Failed(err),
+                ..(*self).clone()
+            },
+        };
+        state.into()
+    }
+}
+
+impl SearchState {
+    /// offset of the next page
+    pub fn offset(&self) -> usize {
+        self.craftsmen.len()
+    }
+
+    pub fn postcode(&self) -> &str {
+        self.query.postalcode.as_deref().unwrap_or_default()
+    }
+}
+
+pub async fn get_craftsmen(
+    query: &SearchQuery,
+    offset: usize,
+    signal: &AbortSignal,
+) -> Result<Vec<Craftsman>, String> {
+    let resp = Request::get("/craftsmen")
+        .query(query.params(offset))
+        .abort_signal(Some(signal))
+        .send()
+        .await
+        .map_err(|err| err.to_string())?;
+    if !resp.ok() {
+        return Err(format!("{} ({})", resp.status(), resp.status_text()));
+    }
+    let response: APIResponse = resp.json().await.map_err(|err| err.to_string())?;
+    Ok(response.craftsmen)
+}
+
+/// location of the postcode to center the map on, if it's known
+pub async fn get_postcode_location(
+    postcode: &str,
+    country: Option<&str>,
+    signal: &AbortSignal,
+) -> Option<(f64, f64)> {
+    let resp = Request::get(&format!("/postcodes/{postcode}"))
+        .query(country.map(|country| ("country", country)))
+        .abort_signal(Some(signal))
+        .send()
+        .await
+        .ok()?;
+    if !resp.ok() {
+        return None;
+    }
+    let location: PostcodeLocation = resp.json().await.ok()?;
+    Some((location.lat, location.lon))
+}
+
+/// services to filter by, none if they can't be loaded
+pub async fn get_services() -> Vec<Service> {
+    let Ok(resp) = Request::get("/services").send().await else {
+        return Vec::new();
+    };
+    if !resp.ok() {
+        return Vec::new();
+    }
+    resp.json::<ServicesResponse>()
+        .await
+        .map(|response| response.services)
+        .unwrap_or_default()
+}
+
+#[derive(Properties, PartialEq)]
+pub struct SentinelProps {
+    pub on_visible: Callback<()>,
+}
+
+/// Placed below the results, it reports when it is scrolled into view. Mounting it again after
+/// each page makes the observer report again if it is still visible.
+#[function_component(Sentinel)]
+pub fn sentinel(props: &SentinelProps) -> Html {
+    let node = use_node_ref();
+    // the observer outlives renders, so it reads the latest callback from here
+    let on_visible = use_mut_ref(|| props.on_visible.clone());
+    *on_visible.borrow_mut() = props.on_visible.clone();
+
+    {
+        let node = node.clone();
+        use_effect_with((), move |_| {
+            let handler = Closure::<dyn FnMut(Array)>::new(move |entries: Array| {
+                let visible = entries.iter().any(|entry| {
+                    entry
+                        .unchecked_into::<IntersectionObserverEntry>()
+                        .is_intersecting()
+                });
+                if visible {
+                    on_visible.borrow().emit(());
+                }
+            });
+
+            let observer = IntersectionObserver::new(handler.as_ref().unchecked_ref()).ok();
+            if let (Some(observer), Some(element)) = (&observer, node.cast::<Element>()) {
+                observer.observe(&element);
+            }
+
+            move || {
+                if let Some(observer) = observer {
+                    observer.disconnect();
+                }
+                drop(handler);
+            }
+        });
+    }
+
+    html! {
+        <div ref={node} style="height: 1px;"></div>
+    }
+}
@@ -0,0 +1,2 @@
+-- optimistic concurrency control for profile updates
+ALTER TABLE profiles ADD COLUMN IF NOT EXISTS version INTEGER NOT NULL DEFAULT 0;
@@ -0,0 +1,32 @@
+-- trade/service categories and the craftsmen offering them
+CREATE TABLE IF NOT EXISTS services (
+    id SERIAL PRIMARY KEY,
+    name TEXT NOT NULL UNIQUE
+);
+
+-- max_driving_distance overrides the radius of the profile for this service (in meters)
+CREATE TABLE IF NOT EXISTS profile_services (
+    profile_id INTEGER NOT NULL REFERENCES profiles (id) ON DELETE CASCADE,
+    service_id INTEGER NOT NULL REFERENCES services (id) ON DELETE CASCADE,
+    max_driving_distance DOUBLE PRECISION,
+    PRIMARY KEY (profile_id, service_id)
+);
+
+-- ranks are materialized for the largest radius of a profile, required_distance is the radius
+-- (in km) needed to cover the postcode, so searches can filter by the radius that applies
+ALTER TABLE filtered_ranks ADD COLUMN IF NOT EXISTS required_distance DOUBLE PRECISION;
+
+UPDATE filtered_ranks
+SET required_distance = GREATEST(
+    filtered_ranks.distance - CASE postcode.postcode_extension_distance_group
+        WHEN 'group_a' THEN 0.0
+        WHEN 'group_b' THEN 2.0
+        ELSE 5.0
+    END,
+    0.0
+)
+FROM postcode
+WHERE filtered_ranks.postcode = postcode.postcode
+    AND filtered_ranks.required_distance IS NULL;
+
+ALTER TABLE filtered_ranks ALTER COLUMN required_distance SET NOT NULL;
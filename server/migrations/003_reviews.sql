@@ -0,0 +1,21 @@
+-- customer reviews, only approved ones count towards the profile score
+DO $$ BEGIN
+    CREATE TYPE review_status AS ENUM ('pending', 'approved', 'rejected');
+EXCEPTION
+    WHEN duplicate_object THEN NULL;
+END $$;
+
+CREATE TABLE IF NOT EXISTS reviews (
+    id SERIAL PRIMARY KEY,
+    profile_id INTEGER NOT NULL REFERENCES profiles (id) ON DELETE CASCADE,
+    rating SMALLINT NOT NULL CHECK (rating BETWEEN 1 AND 5),
+    author TEXT NOT NULL,
+    text TEXT NOT NULL DEFAULT '',
+    status review_status NOT NULL DEFAULT 'pending',
+    created_at TIMESTAMP NOT NULL DEFAULT now()
+);
+
+CREATE INDEX IF NOT EXISTS reviews_profile_id_status ON reviews (profile_id, status);
+
+-- Bayesian rating of the approved reviews mapped onto [0, 1], NULL without any
+ALTER TABLE profiles ADD COLUMN IF NOT EXISTS review_score DOUBLE PRECISION;
@@ -0,0 +1,35 @@
+-- job requests of customers and the craftsmen they are offered to
+DO $$ BEGIN
+    CREATE TYPE job_status AS ENUM ('open', 'offered', 'accepted', 'completed', 'cancelled');
+EXCEPTION
+    WHEN duplicate_object THEN NULL;
+END $$;
+
+DO $$ BEGIN
+    CREATE TYPE offer_status AS ENUM ('offered', 'accepted', 'declined', 'withdrawn');
+EXCEPTION
+    WHEN duplicate_object THEN NULL;
+END $$;
+
+CREATE TABLE IF NOT EXISTS job_requests (
+    id SERIAL PRIMARY KEY,
+    postcode INTEGER NOT NULL REFERENCES postcode (postcode),
+    service_id INTEGER NOT NULL REFERENCES services (id),
+    customer_name TEXT NOT NULL,
+    customer_contact TEXT NOT NULL,
+    description TEXT NOT NULL DEFAULT '',
+    status job_status NOT NULL DEFAULT 'open',
+    profile_id INTEGER REFERENCES profiles (id) ON DELETE SET NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT now(),
+    updated_at TIMESTAMP NOT NULL DEFAULT now()
+);
+
+CREATE TABLE IF NOT EXISTS job_offers (
+    job_request_id INTEGER NOT NULL REFERENCES job_requests (id) ON DELETE CASCADE,
+    profile_id INTEGER NOT NULL REFERENCES profiles (id) ON DELETE CASCADE,
+    status offer_status NOT NULL DEFAULT 'offered',
+    created_at TIMESTAMP NOT NULL DEFAULT now(),
+    PRIMARY KEY (job_request_id, profile_id)
+);
+
+CREATE INDEX IF NOT EXISTS job_offers_profile_id ON job_offers (profile_id, status);
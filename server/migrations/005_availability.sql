@@ -0,0 +1,17 @@
+-- availability slots and vacations published by craftsmen
+DO $$ BEGIN
+    CREATE TYPE availability_kind AS ENUM ('slot', 'vacation');
+EXCEPTION
+    WHEN duplicate_object THEN NULL;
+END $$;
+
+CREATE TABLE IF NOT EXISTS availability (
+    id SERIAL PRIMARY KEY,
+    profile_id INTEGER NOT NULL REFERENCES profiles (id) ON DELETE CASCADE,
+    kind availability_kind NOT NULL,
+    starts_at TIMESTAMP NOT NULL,
+    ends_at TIMESTAMP NOT NULL,
+    CHECK (ends_at > starts_at)
+);
+
+CREATE INDEX IF NOT EXISTS availability_profile_id ON availability (profile_id, kind, ends_at);
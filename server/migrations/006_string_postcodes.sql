@@ -0,0 +1,41 @@
+-- postcodes become strings keyed by country, existing (German) ones get their leading
+-- zeros back
+DO $$
+DECLARE
+    fk RECORD;
+BEGIN
+    IF (SELECT data_type FROM information_schema.columns
+        WHERE table_name = 'postcode' AND column_name = 'postcode') = 'integer' THEN
+
+        FOR fk IN SELECT conrelid::regclass AS tbl, conname FROM pg_constraint
+            WHERE contype = 'f' AND confrelid = 'postcode'::regclass LOOP
+            EXECUTE format('ALTER TABLE %s DROP CONSTRAINT %I', fk.tbl, fk.conname);
+        END LOOP;
+
+        FOR fk IN SELECT conrelid::regclass AS tbl, conname FROM pg_constraint
+            WHERE contype = 'p' AND conrelid IN ('postcode'::regclass, 'filtered_ranks'::regclass) LOOP
+            EXECUTE format('ALTER TABLE %s DROP CONSTRAINT %I', fk.tbl, fk.conname);
+        END LOOP;
+
+        ALTER TABLE postcode
+            ALTER COLUMN postcode TYPE TEXT USING lpad(postcode::text, 5, '0'),
+            ADD COLUMN country_code TEXT NOT NULL DEFAULT 'DE';
+        ALTER TABLE postcode ALTER COLUMN country_code DROP DEFAULT;
+        ALTER TABLE postcode ADD PRIMARY KEY (country_code, postcode);
+
+        ALTER TABLE filtered_ranks
+            ALTER COLUMN postcode TYPE TEXT USING lpad(postcode::text, 5, '0'),
+            ADD COLUMN country_code TEXT NOT NULL DEFAULT 'DE';
+        ALTER TABLE filtered_ranks ALTER COLUMN country_code DROP DEFAULT;
+        ALTER TABLE filtered_ranks ADD PRIMARY KEY (profile_id, country_code, postcode);
+        ALTER TABLE filtered_ranks ADD FOREIGN KEY (country_code, postcode)
+            REFERENCES postcode (country_code, postcode) ON DELETE CASCADE;
+
+        ALTER TABLE job_requests
+            ALTER COLUMN postcode TYPE TEXT USING lpad(postcode::text, 5, '0'),
+            ADD COLUMN country_code TEXT NOT NULL DEFAULT 'DE';
+        ALTER TABLE job_requests ALTER COLUMN country_code DROP DEFAULT;
+        ALTER TABLE job_requests ADD FOREIGN KEY (country_code, postcode)
+            REFERENCES postcode (country_code, postcode);
+    END IF;
+END $$;
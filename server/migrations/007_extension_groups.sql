@@ -0,0 +1,25 @@
+-- extension groups grant postcodes an offset (in km) on top of every driving radius,
+-- postcodes may override the offset of their group
+CREATE TABLE IF NOT EXISTS extension_groups (
+    name TEXT PRIMARY KEY,
+    offset_km DOUBLE PRECISION NOT NULL CHECK (offset_km >= 0)
+);
+
+INSERT INTO extension_groups (name, offset_km)
+VALUES ('group_a', 0.0), ('group_b', 2.0), ('group_c', 5.0)
+ON CONFLICT DO NOTHING;
+
+DO $$ BEGIN
+    IF (SELECT data_type FROM information_schema.columns
+        WHERE table_name = 'postcode'
+            AND column_name = 'postcode_extension_distance_group') = 'USER-DEFINED' THEN
+        ALTER TABLE postcode ALTER COLUMN postcode_extension_distance_group TYPE TEXT
+            USING postcode_extension_distance_group::text;
+        ALTER TABLE postcode ADD FOREIGN KEY (postcode_extension_distance_group)
+            REFERENCES extension_groups (name) ON UPDATE CASCADE;
+        DROP TYPE IF EXISTS in_group;
+    END IF;
+END $$;
+
+ALTER TABLE postcode ADD COLUMN IF NOT EXISTS extension_offset_km DOUBLE PRECISION
+    CHECK (extension_offset_km >= 0);
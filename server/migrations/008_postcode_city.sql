@@ -0,0 +1,2 @@
+-- city names shown next to postcodes in the lookup
+ALTER TABLE postcode ADD COLUMN IF NOT EXISTS city TEXT;
@@ -0,0 +1,13 @@
+-- driving distances (in km) from the routing backend, distance_km is NULL if there is no
+-- route. lat and lon are the location of the profile the distance was computed for
+CREATE TABLE IF NOT EXISTS road_distances (
+    profile_id INTEGER NOT NULL REFERENCES profiles (id) ON DELETE CASCADE,
+    country_code TEXT NOT NULL,
+    postcode TEXT NOT NULL,
+    lat DOUBLE PRECISION NOT NULL,
+    lon DOUBLE PRECISION NOT NULL,
+    distance_km DOUBLE PRECISION,
+    PRIMARY KEY (profile_id, country_code, postcode),
+    FOREIGN KEY (country_code, postcode)
+        REFERENCES postcode (country_code, postcode) ON DELETE CASCADE
+);
@@ -0,0 +1,2 @@
+-- optional GeoJSON polygon or multipolygon a craftsman serves, replaces the radius if present
+ALTER TABLE profiles ADD COLUMN IF NOT EXISTS service_area JSONB;
@@ -0,0 +1,3 @@
+-- GeoJSON polygon or multipolygon outlining the postcode, coverage can be decided by it
+-- instead of the centroid
+ALTER TABLE postcode ADD COLUMN IF NOT EXISTS boundary JSONB;
@@ -0,0 +1,10 @@
+-- settings the stored ranks were computed with, they are rebuilt on startup when these change
+CREATE TABLE IF NOT EXISTS rank_settings (
+    name TEXT PRIMARY KEY,
+    value TEXT NOT NULL
+);
+
+-- ranks stored before the metric was configurable used the law of cosines
+INSERT INTO rank_settings (name, value)
+VALUES ('distance_metric', 'cosines')
+ON CONFLICT (name) DO NOTHING;
@@ -0,0 +1,4 @@
+-- ranks stored before the coverage method was configurable were computed with the centroid
+INSERT INTO rank_settings (name, value)
+VALUES ('coverage_method', 'centroid')
+ON CONFLICT (name) DO NOTHING;
@@ -0,0 +1,14 @@
+-- login codes craftsmen edit their own profile with, only their SHA-256 is stored
+CREATE TABLE IF NOT EXISTS profile_logins (
+    profile_id INTEGER PRIMARY KEY REFERENCES profiles (id) ON DELETE CASCADE,
+    token_hash TEXT NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT now()
+);
+
+-- picture and description craftsmen upload for their profile
+CREATE TABLE IF NOT EXISTS profile_media (
+    profile_id INTEGER PRIMARY KEY REFERENCES profiles (id) ON DELETE CASCADE,
+    picture BYTEA,
+    picture_type TEXT,
+    description TEXT
+);
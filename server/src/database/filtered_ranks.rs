@@ -9,19 +9,23 @@ pub struct Model {
     #[sea_orm(primary_key, auto_increment = false)]
     pub profile_id: i32,
     #[sea_orm(primary_key, auto_increment = false)]
-    pub postcode: i32,
+    pub country_code: String,
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub postcode: String,
     #[sea_orm(column_type = "Double")]
     pub distance: f64,
     #[sea_orm(column_type = "Double")]
     pub rank: f64,
+    #[sea_orm(column_type = "Double")]
+    pub required_distance: f64,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
 pub enum Relation {
     #[sea_orm(
         belongs_to = "super::postcode::Entity",
-        from = "Column::Postcode",
-        to = "super::postcode::Column::Postcode",
+        from = "(Column::CountryCode, Column::Postcode)",
+        to = "(super::postcode::Column::CountryCode, super::postcode::Column::Postcode)",
         on_update = "NoAction",
         on_delete = "Cascade"
     )]
@@ -2,7 +2,18 @@
 
 pub mod prelude;
 
+pub mod availability;
+pub mod extension_groups;
 pub mod filtered_ranks;
+pub mod job_offers;
+pub mod job_requests;
 pub mod postcode;
+pub mod profile_logins;
+pub mod profile_media;
+pub mod profile_services;
 pub mod profiles;
+pub mod rank_settings;
+pub mod reviews;
+pub mod road_distances;
 pub mod sea_orm_active_enums;
+pub mod services;
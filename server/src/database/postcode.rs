@@ -1,6 +1,5 @@
 //! `SeaORM` Entity. Generated by sea-orm-codegen 0.12.2
 
-use super::sea_orm_active_enums::InGroup;
 use sea_orm::entity::prelude::*;
 use serde::{Deserialize, Serialize};
 
@@ -8,20 +7,45 @@ use serde::{Deserialize, Serialize};
 #[sea_orm(table_name = "postcode")]
 pub struct Model {
     #[sea_orm(primary_key, auto_increment = false)]
-    pub postcode: i32,
+    pub country_code: String,
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub postcode: String,
     #[sea_orm(column_type = "Double")]
     pub lon: f64,
     #[sea_orm(column_type = "Double")]
     pub lat: f64,
-    pub postcode_extension_distance_group: InGroup,
+    pub postcode_extension_distance_group: String,
+    #[sea_orm(column_type = "Double", nullable)]
+    pub extension_offset_km: Option<f64>,
+    pub city: Option<String>,
+    #[sea_orm(column_type = "JsonBinary", nullable)]
+    pub boundary: Option<Json>,
     pub created_at: Option<DateTime>,
     pub updated_at: Option<DateTime>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
 pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::extension_groups::Entity",
+        from = "Column::PostcodeExtensionDistanceGroup",
+        to = "super::extension_groups::Column::Name",
+        on_update = "Cascade",
+        on_delete = "NoAction"
+    )]
+    ExtensionGroups,
     #[sea_orm(has_many = "super::filtered_ranks::Entity")]
     FilteredRanks,
+    #[sea_orm(has_many = "super::job_requests::Entity")]
+    JobRequests,
+    #[sea_orm(has_many = "super::road_distances::Entity")]
+    RoadDistances,
+}
+
+impl Related<super::extension_groups::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::ExtensionGroups.def()
+    }
 }
 
 impl Related<super::filtered_ranks::Entity> for Entity {
@@ -30,6 +54,12 @@ impl Related<super::filtered_ranks::Entity> for Entity {
     }
 }
 
+impl Related<super::job_requests::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::JobRequests.def()
+    }
+}
+
 impl Related<super::profiles::Entity> for Entity {
     fn to() -> RelationDef {
         super::filtered_ranks::Relation::Profiles.def()
@@ -39,4 +69,10 @@ impl Related<super::profiles::Entity> for Entity {
     }
 }
 
+impl Related<super::road_distances::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::RoadDistances.def()
+    }
+}
+
 impl ActiveModelBehavior for ActiveModel {}
@@ -1,5 +1,16 @@
 //! `SeaORM` Entity. Generated by sea-orm-codegen 0.12.2
 
+pub use super::availability::Entity as Availability;
+pub use super::extension_groups::Entity as ExtensionGroups;
 pub use super::filtered_ranks::Entity as FilteredRanks;
+pub use super::job_offers::Entity as JobOffers;
+pub use super::job_requests::Entity as JobRequests;
 pub use super::postcode::Entity as Postcode;
+pub use super::profile_logins::Entity as ProfileLogins;
+pub use super::profile_media::Entity as ProfileMedia;
+pub use super::profile_services::Entity as ProfileServices;
 pub use super::profiles::Entity as Profiles;
+pub use super::rank_settings::Entity as RankSettings;
+pub use super::reviews::Entity as Reviews;
+pub use super::road_distances::Entity as RoadDistances;
+pub use super::services::Entity as Services;
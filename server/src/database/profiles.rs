@@ -25,12 +25,39 @@ pub struct Model {
     pub profile_picture_score: f64,
     #[sea_orm(column_type = "Double")]
     pub profile_description_score: f64,
+    pub version: i32,
+    #[sea_orm(column_type = "Double", nullable)]
+    pub review_score: Option<f64>,
+    #[sea_orm(column_type = "JsonBinary", nullable)]
+    pub service_area: Option<Json>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
 pub enum Relation {
+    #[sea_orm(has_many = "super::availability::Entity")]
+    Availability,
     #[sea_orm(has_many = "super::filtered_ranks::Entity")]
     FilteredRanks,
+    #[sea_orm(has_many = "super::job_offers::Entity")]
+    JobOffers,
+    #[sea_orm(has_many = "super::job_requests::Entity")]
+    JobRequests,
+    #[sea_orm(has_one = "super::profile_logins::Entity")]
+    ProfileLogins,
+    #[sea_orm(has_one = "super::profile_media::Entity")]
+    ProfileMedia,
+    #[sea_orm(has_many = "super::profile_services::Entity")]
+    ProfileServices,
+    #[sea_orm(has_many = "super::reviews::Entity")]
+    Reviews,
+    #[sea_orm(has_many = "super::road_distances::Entity")]
+    RoadDistances,
+}
+
+impl Related<super::availability::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Availability.def()
+    }
 }
 
 impl Related<super::filtered_ranks::Entity> for Entity {
@@ -39,6 +66,36 @@ impl Related<super::filtered_ranks::Entity> for Entity {
     }
 }
 
+impl Related<super::job_offers::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::JobOffers.def()
+    }
+}
+
+impl Related<super::job_requests::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::JobRequests.def()
+    }
+}
+
+impl Related<super::profile_logins::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::ProfileLogins.def()
+    }
+}
+
+impl Related<super::profile_media::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::ProfileMedia.def()
+    }
+}
+
+impl Related<super::profile_services::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::ProfileServices.def()
+    }
+}
+
 impl Related<super::postcode::Entity> for Entity {
     fn to() -> RelationDef {
         super::filtered_ranks::Relation::Postcode.def()
@@ -48,4 +105,25 @@ impl Related<super::postcode::Entity> for Entity {
     }
 }
 
+impl Related<super::reviews::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Reviews.def()
+    }
+}
+
+impl Related<super::road_distances::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::RoadDistances.def()
+    }
+}
+
+impl Related<super::services::Entity> for Entity {
+    fn to() -> RelationDef {
+        super::profile_services::Relation::Services.def()
+    }
+    fn via() -> Option<RelationDef> {
+        Some(super::profile_services::Relation::Profiles.def().rev())
+    }
+}
+
 impl ActiveModelBehavior for ActiveModel {}
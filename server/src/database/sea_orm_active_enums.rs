@@ -4,12 +4,53 @@ use sea_orm::entity::prelude::*;
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, PartialEq, Eq, EnumIter, DeriveActiveEnum, Serialize, Deserialize)]
-#[sea_orm(rs_type = "String", db_type = "Enum", enum_name = "in_group")]
-pub enum InGroup {
-    #[sea_orm(string_value = "group_a")]
-    GroupA,
-    #[sea_orm(string_value = "group_b")]
-    GroupB,
-    #[sea_orm(string_value = "group_c")]
-    GroupC,
+#[sea_orm(rs_type = "String", db_type = "Enum", enum_name = "review_status")]
+#[serde(rename_all = "lowercase")]
+pub enum ReviewStatus {
+    #[sea_orm(string_value = "approved")]
+    Approved,
+    #[sea_orm(string_value = "pending")]
+    Pending,
+    #[sea_orm(string_value = "rejected")]
+    Rejected,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumIter, DeriveActiveEnum, Serialize, Deserialize)]
+#[sea_orm(rs_type = "String", db_type = "Enum", enum_name = "job_status")]
+#[serde(rename_all = "lowercase")]
+pub enum JobStatus {
+    #[sea_orm(string_value = "accepted")]
+    Accepted,
+    #[sea_orm(string_value = "cancelled")]
+    Cancelled,
+    #[sea_orm(string_value = "completed")]
+    Completed,
+    #[sea_orm(string_value = "offered")]
+    Offered,
+    #[sea_orm(string_value = "open")]
+    Open,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumIter, DeriveActiveEnum, Serialize, Deserialize)]
+#[sea_orm(rs_type = "String", db_type = "Enum", enum_name = "offer_status")]
+#[serde(rename_all = "lowercase")]
+pub enum OfferStatus {
+    #[sea_orm(string_value = "accepted")]
+    Accepted,
+    #[sea_orm(string_value = "declined")]
+    Declined,
+    #[sea_orm(string_value = "offered")]
+    Offered,
+    #[sea_orm(string_value = "withdrawn")]
+    Withdrawn,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumIter, DeriveActiveEnum, Serialize, Deserialize)]
+#[sea_orm(rs_type = "String", db_type = "Enum", enum_name = "availability_kind")]
+#[serde(rename_all = "lowercase")]
+pub enum AvailabilityKind {
+    #[sea_orm(string_value = "slot")]
+    Slot,
+    #[sea_orm(string_value = "vacation")]
+    Vacation,
 }
@@ -10,12 +10,14 @@ use tower::ServiceExt;
 use tower_http::services::ServeDir;
 
 mod database;
+mod migrations;
 mod rest;
 mod traits;
 mod utils;
 
 use axum::{
-    routing::{get, patch},
+    middleware,
+    routing::{delete, get, patch, post, put},
     Router,
 };
 
@@ -24,8 +26,110 @@ async fn main() -> Result<(), DbErr> {
     let state = rest::app_state::init_state().await?;
 
     let router: Router = Router::new()
-        .route("/craftsmen", get(rest::get_craftsmen::handler))
-        .route("/craftsmen/:id", patch(rest::patch_craftsmen::handler))
+        .route(
+            "/craftsmen",
+            get(rest::get_craftsmen::handler).patch(rest::patch_craftsmen_batch::handler),
+        )
+        .route(
+            "/craftsmen/:id",
+            get(rest::get_craftsman::handler).patch(rest::patch_craftsmen::handler),
+        )
+        .route("/craftsmen/:id/services", get(rest::profile_services::list))
+        .route(
+            "/craftsmen/:id/services/:service_id",
+            put(rest::profile_services::put).delete(rest::profile_services::delete),
+        )
+        .route(
+            "/craftsmen/:id/reviews",
+            get(rest::reviews::list).post(rest::reviews::create),
+        )
+        .route("/reviews", get(rest::reviews::queue))
+        .route("/reviews/:id", patch(rest::reviews::moderate))
+        .route(
+            "/services",
+            get(rest::services::list).post(rest::services::create),
+        )
+        .route(
+            "/services/:id",
+            get(rest::services::get)
+                .patch(rest::services::update)
+                .delete(rest::services::delete),
+        )
+        .route(
+            "/craftsmen/:id/availability",
+            get(rest::availability::list).post(rest::availability::create),
+        )
+        .route(
+            "/craftsmen/:id/availability/:availability_id",
+            delete(rest::availability::delete),
+        )
+        .route(
+            "/craftsmen/:id/coverage.geojson",
+            get(rest::coverage::geojson),
+        )
+        .route(
+            "/craftsmen/:id/coverage/preview",
+            get(rest::coverage::preview),
+        )
+        .route("/craftsmen/:id/login", post(rest::profile_login::check))
+        .route(
+            "/craftsmen/:id/picture",
+            get(rest::profile_media::get_picture).put(rest::profile_media::put_picture),
+        )
+        .route(
+            "/craftsmen/:id/description",
+            get(rest::profile_media::get_description).put(rest::profile_media::put_description),
+        )
+        .route(
+            "/craftsmen/:id/service-area",
+            get(rest::service_area::get)
+                .put(rest::service_area::put)
+                .delete(rest::service_area::delete),
+        )
+        .route("/craftsmen/:id/job-offers", get(rest::jobs::offered_to))
+        .route("/jobs", post(rest::jobs::create))
+        .route("/jobs/:id", get(rest::jobs::get))
+        .route("/jobs/:id/offers", post(rest::jobs::reoffer))
+        .route(
+            "/jobs/:id/offers/:profile_id/accept",
+            post(rest::jobs::accept),
+        )
+        .route(
+            "/jobs/:id/offers/:profile_id/decline",
+            post(rest::jobs::decline),
+        )
+        .route("/jobs/:id/complete", post(rest::jobs::complete))
+        .route("/jobs/:id/cancel", post(rest::jobs::cancel))
+        .route("/admin/coverage-gaps", get(rest::coverage_gaps::handler))
+        .route(
+            "/admin/craftsmen/:id/login",
+            post(rest::profile_login::issue),
+        )
+        .route("/admin/extension-groups", get(rest::extension_groups::list))
+        .route(
+            "/admin/extension-groups/:name",
+            put(rest::extension_groups::put).delete(rest::extension_groups::delete),
+        )
+        .route(
+            "/admin/postcodes/reload",
+            post(rest::reload_postcodes::handler),
+        )
+        .route(
+            "/admin/postcodes/boundaries",
+            post(rest::postcode_boundaries::import),
+        )
+        .route(
+            "/admin/postcodes/:country/:postcode/extension",
+            put(rest::extension_groups::put_postcode),
+        )
+        .route("/postcodes", get(rest::postcodes::list))
+        .route("/postcodes/:code", get(rest::postcodes::get))
+        .route("/tiles/:z/:x/:y", get(rest::tiles::handler))
+        .route("/metrics", get(rest::get_metrics::handler))
+        .route_layer(middleware::from_fn_with_state(
+            state.clone(),
+            rest::idempotency::middleware,
+        ))
         .fallback_service(get(|req: Request<Body>| async move {
             let res = ServeDir::new("./dist").oneshot(req).await.unwrap(); // serve dir is infallible
             let status = res.status();
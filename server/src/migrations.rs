@@ -0,0 +1,28 @@
+use sea_orm::{ConnectionTrait, DatabaseConnection, DbErr};
+
+// the schema itself comes from the database dump, these are the changes made since.
+// every migration has to be idempotent, as all of them are applied on each startup
+const MIGRATIONS: &[&str] = &[
+    include_str!("../migrations/001_profile_version.sql"),
+    include_str!("../migrations/002_services.sql"),
+    include_str!("../migrations/003_reviews.sql"),
+    include_str!("../migrations/004_job_requests.sql"),
+    include_str!("../migrations/005_availability.sql"),
+    include_str!("../migrations/006_string_postcodes.sql"),
+    include_str!("../migrations/007_extension_groups.sql"),
+    include_str!("../migrations/008_postcode_city.sql"),
+    include_str!("../migrations/009_road_distances.sql"),
+    include_str!("../migrations/010_service_areas.sql"),
+    include_str!("../migrations/011_postcode_boundaries.sql"),
+    include_str!("../migrations/012_rank_settings.sql"),
+    include_str!("../migrations/013_coverage_method_setting.sql"),
+    include_str!("../migrations/014_profile_self_service.sql"),
+];
+
+pub async fn run(db: &DatabaseConnection) -> Result<(), DbErr> {
+    for migration in MIGRATIONS {
+        db.execute_unprepared(migration).await?;
+    }
+
+    Ok(())
+}
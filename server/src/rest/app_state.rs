@@ -1,32 +1,227 @@
 use dotenv::dotenv;
-use sea_orm::{Database, EntityTrait};
-use sea_orm::{DatabaseConnection, DbErr};
+use sea_orm::sea_query::OnConflict;
+use sea_orm::{ActiveValue, Database, EntityTrait, TransactionTrait};
+use sea_orm::{DatabaseConnection, DatabaseTransaction, DbErr};
+use std::collections::HashMap;
 use std::env::var;
-use std::sync::Arc;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
 
-use crate::database::postcode;
-use crate::utils::postcode_utils::Postcode;
+use crate::database::rank_settings;
+use crate::migrations;
+use crate::rest::patch_craftsmen::materialize_postcodes;
+use crate::traits::simple_disctance::DistanceMetric;
+use crate::utils::availability::AvailabilityPenalty;
+use crate::utils::idempotency::IdempotencyStore;
+use crate::utils::postcode_utils::{load_postcodes, CoverageMethod, Postcode, PostcodeId};
+use crate::utils::postcode_versions::PostcodeVersions;
+use crate::utils::result_cache::ResultCache;
+use crate::utils::routing::{Distances, OsrmClient, RoutingProvider};
+
+const DEFAULT_CACHE_SIZE: usize = 256;
+const DEFAULT_CACHE_TTL_SECS: u64 = 300;
+const DEFAULT_IDEMPOTENCY_WINDOW_SECS: u64 = 24 * 60 * 60;
+const DEFAULT_AVAILABILITY_PENALTY: f64 = 1.0;
+const DEFAULT_AVAILABILITY_HORIZON_DAYS: i32 = 14;
+const DEFAULT_ROUTING_PROFILE: &str = "driving";
+const DEFAULT_ROUTING_TIMEOUT_SECS: u64 = 10;
+const DEFAULT_ROUTING_BUDGET_SECS: u64 = 10;
+
+/// names of the settings in `rank_settings`
+const DISTANCE_METRIC_SETTING: &str = "distance_metric";
+const COVERAGE_METHOD_SETTING: &str = "coverage_method";
 
 #[derive(Clone)]
 pub struct AppState {
     pub db: DatabaseConnection,
-    pub postcodes: Arc<Vec<Postcode>>,
+    pub postcodes: Arc<RwLock<Arc<Vec<Postcode>>>>,
+    pub versions: Arc<PostcodeVersions>,
+    pub cache: Arc<ResultCache>,
+    pub idempotency: Arc<IdempotencyStore>,
+    pub availability_penalty: AvailabilityPenalty,
+    pub distances: Arc<Distances>,
+}
+
+impl AppState {
+    /// snapshot of the known postcodes, unaffected by later reloads
+    pub fn postcodes(&self) -> Arc<Vec<Postcode>> {
+        self.postcodes.read().unwrap().clone()
+    }
+
+    /// Commits the transaction that stored the postcodes and rebuilt their ranks. They are
+    /// swapped in before, so profile updates waiting on the locks of the rebuild already see
+    /// them, and swapped back if the commit fails.
+    pub async fn commit_postcodes(
+        &self,
+        postcodes: Vec<Postcode>,
+        txn: DatabaseTransaction,
+    ) -> Result<(), DbErr> {
+        let previous =
+            std::mem::replace(&mut *self.postcodes.write().unwrap(), Arc::new(postcodes));
+
+        txn.commit()
+            .await
+            .inspect_err(|_| *self.postcodes.write().unwrap() = previous)
+    }
+
+    /// to be called after a commit that changed `filtered_ranks` rows of the given postcodes
+    pub fn postcodes_changed(&self, postcodes: Vec<PostcodeId>) {
+        self.cache.invalidate(&postcodes);
+        self.versions.bump(postcodes);
+    }
 }
 
 pub async fn init_state() -> Result<AppState, DbErr> {
     dotenv().ok();
     let db_url = var("DATABASE_URL").expect("DATABASE_URL missing from .env");
     let db = Database::connect(&db_url).await?;
+    migrations::run(&db).await?;
 
-    let postcodes: Vec<Postcode> = postcode::Entity::find()
-        .all(&db)
-        .await
-        .unwrap()
+    let postcodes = load_postcodes(&db).await?;
+
+    let versions = Arc::new(PostcodeVersions::new());
+
+    let cache_size = var("RESULT_CACHE_SIZE")
+        .ok()
+        .and_then(|size| size.parse().ok())
+        .unwrap_or(DEFAULT_CACHE_SIZE);
+    let cache_ttl = var("RESULT_CACHE_TTL_SECS")
+        .ok()
+        .and_then(|ttl| ttl.parse().ok())
+        .unwrap_or(DEFAULT_CACHE_TTL_SECS);
+    let cache = Arc::new(ResultCache::new(cache_size, Duration::from_secs(cache_ttl)));
+
+    let idempotency_window = var("IDEMPOTENCY_WINDOW_SECS")
+        .ok()
+        .and_then(|window| window.parse().ok())
+        .unwrap_or(DEFAULT_IDEMPOTENCY_WINDOW_SECS);
+    let idempotency = Arc::new(IdempotencyStore::new(Duration::from_secs(
+        idempotency_window,
+    )));
+
+    // off by default, e.g. 0.5 halves the rank of craftsmen that are booked out
+    let availability_penalty = AvailabilityPenalty {
+        factor: var("AVAILABILITY_PENALTY")
+            .ok()
+            .map(|factor| {
+                factor
+                    .parse()
+                    .ok()
+                    .filter(|factor| (0.0..=1.0).contains(factor))
+                    .unwrap_or_else(|| {
+                        panic!("AVAILABILITY_PENALTY {factor} isn't between 0 and 1")
+                    })
+            })
+            .unwrap_or(DEFAULT_AVAILABILITY_PENALTY),
+        horizon_days: var("AVAILABILITY_HORIZON_DAYS")
+            .ok()
+            .and_then(|days| days.parse().ok())
+            .unwrap_or(DEFAULT_AVAILABILITY_HORIZON_DAYS),
+    };
+
+    // cosines, haversine or vincenty, a typo would silently change all distances
+    let distance_metric: DistanceMetric = var("DISTANCE_METRIC")
+        .ok()
+        .map(|metric| {
+            metric
+                .parse()
+                .unwrap_or_else(|_| panic!("unknown DISTANCE_METRIC {metric}"))
+        })
+        .unwrap_or_default();
+
+    // driving distances are only used if a routing backend is configured
+    let routing = var("OSRM_URL").ok().map(|url| {
+        let profile = var("OSRM_PROFILE").unwrap_or_else(|_| DEFAULT_ROUTING_PROFILE.to_owned());
+        let timeout = var("ROUTING_TIMEOUT_SECS")
+            .ok()
+            .and_then(|timeout| timeout.parse().ok())
+            .unwrap_or(DEFAULT_ROUTING_TIMEOUT_SECS);
+
+        Arc::new(OsrmClient::new(
+            &url,
+            &profile,
+            Duration::from_secs(timeout),
+        )) as Arc<dyn RoutingProvider>
+    });
+    // for all backend requests of one update together, e.g. of every profile after a reload
+    let routing_budget = var("ROUTING_BUDGET_SECS")
+        .ok()
+        .and_then(|budget| budget.parse().ok())
+        .unwrap_or(DEFAULT_ROUTING_BUDGET_SECS);
+    // centroid or boundary, a typo would silently change which postcodes are covered
+    let coverage: CoverageMethod = var("COVERAGE_METHOD")
+        .ok()
+        .map(|method| {
+            method
+                .parse()
+                .unwrap_or_else(|_| panic!("unknown COVERAGE_METHOD {method}"))
+        })
+        .unwrap_or_default();
+
+    let distances = Arc::new(Distances {
+        metric: distance_metric,
+        coverage,
+        routing,
+        routing_budget: Duration::from_secs(routing_budget),
+    });
+
+    rebuild_for_settings(&db, &postcodes, &distances).await?;
+    let postcodes = Arc::new(RwLock::new(Arc::new(postcodes)));
+
+    Ok(AppState {
+        db,
+        postcodes,
+        versions,
+        cache,
+        idempotency,
+        availability_penalty,
+        distances,
+    })
+}
+
+/// Rebuilds all ranks if they were computed with another distance metric or coverage method
+/// than the configured ones, as ranks of different settings can't be compared.
+async fn rebuild_for_settings(
+    db: &DatabaseConnection,
+    postcodes: &[Postcode],
+    distances: &Distances,
+) -> Result<(), DbErr> {
+    let settings = [
+        (DISTANCE_METRIC_SETTING, distances.metric.to_string()),
+        (COVERAGE_METHOD_SETTING, distances.coverage.to_string()),
+    ];
+
+    let stored: HashMap<String, String> = rank_settings::Entity::find()
+        .all(db)
+        .await?
         .into_iter()
-        .map(|postcode| postcode.into())
+        .map(|setting| (setting.name, setting.value))
         .collect();
+    if settings
+        .iter()
+        .all(|(name, value)| stored.get(*name) == Some(value))
+    {
+        return Ok(());
+    }
+
+    let txn = db.begin().await?;
+
+    let postcodes: Vec<&Postcode> = postcodes.iter().collect();
+    materialize_postcodes(&postcodes, distances, &txn)
+        .await
+        .map_err(|status| DbErr::Custom(format!("rebuilding ranks failed: {status}")))?;
 
-    let postcodes = Arc::new(postcodes);
+    rank_settings::Entity::insert_many(settings.map(|(name, value)| rank_settings::ActiveModel {
+        name: ActiveValue::Set(name.to_owned()),
+        value: ActiveValue::Set(value),
+    }))
+    .on_conflict(
+        OnConflict::column(rank_settings::Column::Name)
+            .update_column(rank_settings::Column::Value)
+            .to_owned(),
+    )
+    .exec(&txn)
+    .await?;
 
-    Ok(AppState { db, postcodes })
+    txn.commit().await
 }
@@ -0,0 +1,124 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+};
+use chrono::{NaiveDateTime, Utc};
+use sea_orm::{ActiveModelTrait, ActiveValue, ColumnTrait, EntityTrait, QueryFilter, QueryOrder};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    database::{availability, profiles, sea_orm_active_enums::AvailabilityKind},
+    utils::validation::{Validate, ValidationErrors, Validator},
+};
+
+use super::app_state::AppState;
+use super::extract::ValidJson;
+use super::patch_craftsmen::ranked_postcodes;
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NewPeriod {
+    kind: AvailabilityKind,
+    starts_at: NaiveDateTime,
+    ends_at: NaiveDateTime,
+}
+
+impl Validate for NewPeriod {
+    fn validate(&self, state: &AppState) -> Result<(), ValidationErrors> {
+        Validator::new(state)
+            .check(
+                "endsAt",
+                self.ends_at > self.starts_at,
+                "must be after startsAt",
+            )
+            .finish()
+    }
+}
+
+#[derive(Serialize)]
+pub struct Response {
+    availability: Vec<availability::Model>,
+}
+
+/// Search results depend on the availability, so the postcodes the craftsman is ranked for
+/// have to be invalidated after every change.
+async fn availability_changed(state: &AppState, profile_id: i32) -> Result<(), StatusCode> {
+    let postcodes = ranked_postcodes(profile_id, &state.db).await?;
+    state.postcodes_changed(postcodes);
+    Ok(())
+}
+
+/// slots and vacations of a craftsman that aren't over yet
+pub async fn list(
+    Path(id): Path<i32>,
+    State(AppState { db, .. }): State<AppState>,
+) -> Result<String, StatusCode> {
+    profiles::Entity::find_by_id(id)
+        .one(&db)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    let availability = availability::Entity::find()
+        .filter(availability::Column::ProfileId.eq(id))
+        .filter(availability::Column::EndsAt.gt(Utc::now().naive_utc()))
+        .order_by_asc(availability::Column::StartsAt)
+        .all(&db)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    serde_json::to_string(&Response { availability }).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)
+}
+
+pub async fn create(
+    Path(id): Path<i32>,
+    State(state): State<AppState>,
+    ValidJson(NewPeriod {
+        kind,
+        starts_at,
+        ends_at,
+    }): ValidJson<NewPeriod>,
+) -> Result<(StatusCode, String), StatusCode> {
+    profiles::Entity::find_by_id(id)
+        .one(&state.db)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    let period = availability::ActiveModel {
+        profile_id: ActiveValue::Set(id),
+        kind: ActiveValue::Set(kind),
+        starts_at: ActiveValue::Set(starts_at),
+        ends_at: ActiveValue::Set(ends_at),
+        ..Default::default()
+    }
+    .insert(&state.db)
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    availability_changed(&state, id).await?;
+
+    let body = serde_json::to_string(&period).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok((StatusCode::CREATED, body))
+}
+
+pub async fn delete(
+    Path((id, period_id)): Path<(i32, i32)>,
+    State(state): State<AppState>,
+) -> Result<StatusCode, StatusCode> {
+    let deleted = availability::Entity::delete_many()
+        .filter(availability::Column::Id.eq(period_id))
+        .filter(availability::Column::ProfileId.eq(id))
+        .exec(&state.db)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    if deleted.rows_affected == 0 {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    availability_changed(&state, id).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
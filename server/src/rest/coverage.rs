@@ -0,0 +1,153 @@
+use axum::{
+    extract::{Path, State},
+    http::{header, StatusCode},
+    response::IntoResponse,
+};
+use sea_orm::{ColumnTrait, EntityTrait, QueryFilter, QueryOrder};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::{
+    database::{filtered_ranks, profiles},
+    utils::geometry::{Feature, FeatureCollection, FeatureGeometry, GEOJSON},
+    utils::postcode_utils::PostcodeId,
+    utils::validation::{self, Validate, ValidationErrors, Validator},
+};
+
+use super::app_state::AppState;
+use super::extract::ValidQuery;
+use super::patch_craftsmen::patch_filters;
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PreviewQuery {
+    /// radius to preview in meters
+    max_driving_distance: f64,
+}
+
+impl Validate for PreviewQuery {
+    fn validate(&self, state: &AppState) -> Result<(), ValidationErrors> {
+        Validator::new(state)
+            .field(
+                "maxDrivingDistance",
+                &self.max_driving_distance,
+                &[
+                    validation::finite,
+                    validation::positive,
+                    validation::driving_distance,
+                ],
+            )
+            .finish()
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Preview {
+    max_driving_distance: f64,
+    /// postcodes the craftsman would be ranked for
+    postcodes: usize,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CoverageProperties {
+    #[serde(flatten)]
+    id: PostcodeId,
+    city: Option<String>,
+    distance: f64,
+    rank: f64,
+    required_distance: f64,
+}
+
+/// The postcodes the craftsman is ranked for, as boundaries where known and as centroids
+/// otherwise. Ranks are materialized for the largest radius, so a postcode may only be covered
+/// for some of the services.
+pub async fn geojson(
+    Path(id): Path<i32>,
+    State(state): State<AppState>,
+) -> Result<impl IntoResponse, StatusCode> {
+    profiles::Entity::find_by_id(id)
+        .one(&state.db)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    let ranks = filtered_ranks::Entity::find()
+        .filter(filtered_ranks::Column::ProfileId.eq(id))
+        .order_by_asc(filtered_ranks::Column::Distance)
+        .all(&state.db)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let snapshot = state.postcodes();
+    let postcodes: HashMap<&PostcodeId, _> = snapshot
+        .iter()
+        .map(|postcode| (postcode.id(), postcode))
+        .collect();
+
+    // ranks of postcodes removed by a reload that wasn't rebuilt yet are left out
+    let features = ranks
+        .into_iter()
+        .filter_map(|rank| {
+            let id = rank.postcode_id();
+            let postcode = postcodes.get(&id)?;
+
+            let geometry = match postcode.boundary() {
+                Some(boundary) => FeatureGeometry::Area(boundary),
+                None => FeatureGeometry::Point(postcode.location().into()),
+            };
+
+            Some(Feature {
+                geometry,
+                properties: CoverageProperties {
+                    id,
+                    city: postcode.info().city,
+                    distance: rank.distance,
+                    rank: rank.rank,
+                    required_distance: rank.required_distance,
+                },
+            })
+        })
+        .collect();
+
+    let body = serde_json::to_string(&FeatureCollection { features })
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(([(header::CONTENT_TYPE, GEOJSON)], body))
+}
+
+/// How many postcodes the craftsman would cover with another radius, without storing it.
+/// Distances are straight lines, so the count can be a bit higher than with a routing backend.
+/// Radii of services aren't taken into account.
+pub async fn preview(
+    Path(id): Path<i32>,
+    ValidQuery(PreviewQuery {
+        max_driving_distance,
+    }): ValidQuery<PreviewQuery>,
+    State(state): State<AppState>,
+) -> Result<String, StatusCode> {
+    let profile = profiles::Entity::find_by_id(id)
+        .one(&state.db)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    let profile = profiles::Model {
+        max_driving_distance,
+        ..profile
+    };
+    let patch = patch_filters(&profile, None, &state.distances);
+
+    let postcodes = state
+        .postcodes()
+        .iter()
+        .filter(|postcode| postcode.get_model_opt(&patch).is_some())
+        .count();
+
+    serde_json::to_string(&Preview {
+        max_driving_distance,
+        postcodes,
+    })
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)
+}
@@ -0,0 +1,310 @@
+use axum::{
+    extract::State,
+    http::{header, StatusCode},
+    response::{IntoResponse, Response as AxumResponse},
+};
+use geoutils::Location;
+use sea_orm::{
+    ColumnTrait, EntityTrait, JoinType, QueryFilter, QuerySelect, QueryTrait, RelationTrait,
+};
+use sea_query::{Expr, Func};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fmt::Write;
+
+use crate::{
+    database::{filtered_ranks, profiles},
+    traits::simple_disctance::SimpleDistance,
+    utils::postcode_utils::{PostcodeId, PostcodeInfo},
+    utils::validation::{Validate, ValidationErrors, Validator},
+};
+
+use super::app_state::AppState;
+use super::extract::ValidQuery;
+use super::patch_craftsmen::CHUNK_SIZE;
+
+const DEFAULT_THRESHOLD: u64 = 3;
+
+/// uncovered craftsmen listed per postcode
+const NEAREST: usize = 3;
+
+#[derive(Deserialize, Default, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum Format {
+    #[default]
+    Json,
+    Csv,
+}
+
+#[derive(Deserialize)]
+pub struct ReqQuery {
+    /// postcodes with fewer craftsmen than this are reported
+    threshold: Option<u64>,
+    /// ISO 3166-1 alpha-2 code, all countries if missing
+    country: Option<String>,
+    #[serde(default)]
+    format: Format,
+}
+
+impl Validate for ReqQuery {
+    fn validate(&self, state: &AppState) -> Result<(), ValidationErrors> {
+        Validator::new(state)
+            .check("threshold", self.threshold != Some(0), "must be positive")
+            .finish()
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UncoveredCraftsman {
+    id: i32,
+    name: String,
+    /// km to the centroid of the postcode
+    distance: f64,
+    /// in meters, like everywhere else
+    max_driving_distance: f64,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Gap {
+    #[serde(flatten)]
+    info: PostcodeInfo,
+    /// craftsmen that show up in unfiltered searches for the postcode
+    craftsmen: u64,
+    best_rank: Option<f64>,
+    average_rank: Option<f64>,
+    nearest_uncovered: Vec<UncoveredCraftsman>,
+}
+
+#[derive(Serialize)]
+pub struct Response {
+    threshold: u64,
+    gaps: Vec<Gap>,
+}
+
+/// Condition on `filtered_ranks` joined with `profiles` for ranks within the radius of the
+/// profile, i.e. the ones unfiltered searches return.
+fn covered() -> sea_query::SimpleExpr {
+    Expr::col((
+        filtered_ranks::Entity,
+        filtered_ranks::Column::RequiredDistance,
+    ))
+    .lte(Expr::col((profiles::Entity, profiles::Column::MaxDrivingDistance)).div(1000.0))
+}
+
+/// Postcodes with fewer craftsmen than the threshold, along with the craftsmen closest to them
+/// that don't cover them yet. Ranks are the materialized ones, without availability penalty.
+pub async fn handler(
+    ValidQuery(ReqQuery {
+        threshold,
+        country,
+        format,
+    }): ValidQuery<ReqQuery>,
+    State(state): State<AppState>,
+) -> Result<AxumResponse, StatusCode> {
+    let threshold = threshold.unwrap_or(DEFAULT_THRESHOLD);
+    let country = country.map(|country| country.trim().to_uppercase());
+
+    let counts: HashMap<PostcodeId, (i64, Option<f64>, Option<f64>)> =
+        filtered_ranks::Entity::find()
+            .select_only()
+            .column(filtered_ranks::Column::CountryCode)
+            .column(filtered_ranks::Column::Postcode)
+            .column_as(filtered_ranks::Column::ProfileId.count(), "craftsmen")
+            .column_as(filtered_ranks::Column::Rank.max(), "best_rank")
+            .column_as(
+                Expr::expr(Func::avg(Expr::col(filtered_ranks::Column::Rank))),
+                "average_rank",
+            )
+            .join(
+                JoinType::InnerJoin,
+                filtered_ranks::Relation::Profiles.def(),
+            )
+            .filter(covered())
+            .apply_if(country.as_ref(), |query, country| {
+                query.filter(filtered_ranks::Column::CountryCode.eq(country))
+            })
+            .group_by(filtered_ranks::Column::CountryCode)
+            .group_by(filtered_ranks::Column::Postcode)
+            .into_tuple::<(String, String, i64, Option<f64>, Option<f64>)>()
+            .all(&state.db)
+            .await
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+            .into_iter()
+            .map(|(country_code, postcode, count, best, average)| {
+                (
+                    PostcodeId::from((country_code, postcode)),
+                    (count, best, average),
+                )
+            })
+            .collect();
+
+    // postcodes nobody is ranked for don't show up in `filtered_ranks` at all
+    let snapshot = state.postcodes();
+    let gaps: Vec<_> = snapshot
+        .iter()
+        .filter(|postcode| {
+            country
+                .as_ref()
+                .is_none_or(|country| &postcode.id().country_code == country)
+        })
+        .filter(|postcode| {
+            let count = counts.get(postcode.id()).map_or(0, |(count, ..)| *count);
+            (count as u64) < threshold
+        })
+        .collect();
+
+    // craftsmen already covering one of the gaps, they aren't listed as uncovered for it
+    let mut covering: HashSet<(i32, PostcodeId)> = HashSet::new();
+    let ids: Vec<_> = gaps
+        .iter()
+        .map(|postcode| postcode.id())
+        .filter(|id| counts.contains_key(*id))
+        .map(|id| (id.country_code.clone(), id.postcode.clone()))
+        .collect();
+
+    for chunk in ids.chunks(CHUNK_SIZE) {
+        let rows: Vec<(i32, String, String)> = filtered_ranks::Entity::find()
+            .select_only()
+            .column(filtered_ranks::Column::ProfileId)
+            .column(filtered_ranks::Column::CountryCode)
+            .column(filtered_ranks::Column::Postcode)
+            .join(
+                JoinType::InnerJoin,
+                filtered_ranks::Relation::Profiles.def(),
+            )
+            .filter(covered())
+            .filter(
+                Expr::tuple([
+                    Expr::col(filtered_ranks::Column::CountryCode).into(),
+                    Expr::col(filtered_ranks::Column::Postcode).into(),
+                ])
+                .in_tuples(chunk.iter().cloned()),
+            )
+            .into_tuple()
+            .all(&state.db)
+            .await
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+        covering.extend(
+            rows.into_iter().map(|(id, country_code, postcode)| {
+                (id, PostcodeId::from((country_code, postcode)))
+            }),
+        );
+    }
+
+    let craftsmen = profiles::Entity::find()
+        .all(&state.db)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let metric = state.distances.metric;
+    let gaps: Vec<Gap> = gaps
+        .into_iter()
+        .map(|postcode| {
+            let id = postcode.id();
+
+            let mut nearest: Vec<(f64, &profiles::Model)> = craftsmen
+                .iter()
+                .filter(|profile| !covering.contains(&(profile.id, id.clone())))
+                .map(|profile| {
+                    let location = Location::new(profile.lat, profile.lon);
+                    let distance = postcode.location().calculate_distance_km(&location, metric);
+                    (distance, profile)
+                })
+                .collect();
+
+            // only the nearest few are sorted, not every craftsman for every gap
+            let by_distance = |a: &(f64, _), b: &(f64, _)| a.0.total_cmp(&b.0);
+            if nearest.len() > NEAREST {
+                nearest.select_nth_unstable_by(NEAREST, by_distance);
+                nearest.truncate(NEAREST);
+            }
+            nearest.sort_by(by_distance);
+
+            let nearest = nearest
+                .into_iter()
+                .map(|(distance, profile)| UncoveredCraftsman {
+                    id: profile.id,
+                    name: format!("{} {}", profile.first_name, profile.last_name),
+                    distance,
+                    max_driving_distance: profile.max_driving_distance,
+                })
+                .collect();
+
+            let (count, best_rank, average_rank) =
+                counts.get(id).copied().unwrap_or((0, None, None));
+
+            Gap {
+                info: postcode.info(),
+                craftsmen: count as u64,
+                best_rank,
+                average_rank,
+                nearest_uncovered: nearest,
+            }
+        })
+        .collect();
+
+    let response = match format {
+        Format::Json => serde_json::to_string(&Response { threshold, gaps })
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+            .into_response(),
+        Format::Csv => (
+            [
+                (header::CONTENT_TYPE, "text/csv"),
+                (
+                    header::CONTENT_DISPOSITION,
+                    "attachment; filename=\"coverage-gaps.csv\"",
+                ),
+            ],
+            to_csv(&gaps).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?,
+        )
+            .into_response(),
+    };
+
+    Ok(response)
+}
+
+/// one row per gap, the uncovered craftsmen as `id:distance` separated by spaces
+fn to_csv(gaps: &[Gap]) -> Result<String, std::fmt::Error> {
+    let mut csv = String::from(
+        "country_code,postcode,city,craftsmen,best_rank,average_rank,nearest_uncovered\n",
+    );
+
+    for gap in gaps {
+        let nearest = gap
+            .nearest_uncovered
+            .iter()
+            .map(|craftsman| format!("{}:{:.1}", craftsman.id, craftsman.distance))
+            .collect::<Vec<_>>()
+            .join(" ");
+
+        writeln!(
+            csv,
+            "{},{},{},{},{},{},{}",
+            csv_field(&gap.info.id.country_code),
+            csv_field(&gap.info.id.postcode),
+            csv_field(gap.info.city.as_deref().unwrap_or_default()),
+            gap.craftsmen,
+            gap.best_rank
+                .map(|rank| rank.to_string())
+                .unwrap_or_default(),
+            gap.average_rank
+                .map(|rank| rank.to_string())
+                .unwrap_or_default(),
+            nearest,
+        )?;
+    }
+
+    Ok(csv)
+}
+
+/// quotes fields that would otherwise break the row (RFC 4180)
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_owned()
+    }
+}
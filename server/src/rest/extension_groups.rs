@@ -0,0 +1,208 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+};
+use sea_orm::{
+    ActiveModelTrait, ActiveValue, ColumnTrait, DatabaseTransaction, DbErr, EntityTrait,
+    QueryFilter, QueryOrder, QuerySelect, SqlErr, TransactionTrait,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+use crate::{
+    database::{extension_groups, postcode},
+    utils::postcode_utils::{load_postcodes, PostcodeId},
+    utils::validation::{self, Validate, ValidationErrors, Validator},
+};
+
+use super::app_state::AppState;
+use super::extract::ValidJson;
+use super::patch_craftsmen::materialize_postcodes;
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GroupBody {
+    offset_km: f64,
+}
+
+impl Validate for GroupBody {
+    fn validate(&self, state: &AppState) -> Result<(), ValidationErrors> {
+        Validator::new(state)
+            .field(
+                "offsetKm",
+                &self.offset_km,
+                &[validation::finite, validation::non_negative],
+            )
+            .finish()
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PostcodeBody {
+    extension_group: String,
+    /// overrides the offset of the group if present
+    offset_km: Option<f64>,
+}
+
+impl Validate for PostcodeBody {
+    fn validate(&self, state: &AppState) -> Result<(), ValidationErrors> {
+        Validator::new(state)
+            .field(
+                "extensionGroup",
+                self.extension_group.as_str(),
+                &[validation::not_blank],
+            )
+            .optional(
+                "offsetKm",
+                &self.offset_km,
+                &[validation::finite, validation::non_negative],
+            )
+            .finish()
+    }
+}
+
+#[derive(Serialize)]
+pub struct Response {
+    groups: Vec<extension_groups::Model>,
+}
+
+fn map_write_err(err: DbErr) -> StatusCode {
+    match err.sql_err() {
+        // groups still in use, or postcodes assigned to a group that doesn't exist
+        Some(SqlErr::ForeignKeyConstraintViolation(_)) => StatusCode::CONFLICT,
+        _ => StatusCode::INTERNAL_SERVER_ERROR,
+    }
+}
+
+pub async fn list(State(AppState { db, .. }): State<AppState>) -> Result<String, StatusCode> {
+    let groups = extension_groups::Entity::find()
+        .order_by_asc(extension_groups::Column::Name)
+        .all(&db)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    serde_json::to_string(&Response { groups }).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)
+}
+
+/// Creates the group or changes its offset. The ranks of all postcodes in the group that
+/// don't override the offset are recomputed.
+pub async fn put(
+    Path(name): Path<String>,
+    State(state): State<AppState>,
+    ValidJson(GroupBody { offset_km }): ValidJson<GroupBody>,
+) -> Result<String, StatusCode> {
+    let txn = state
+        .db
+        .begin()
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let group = extension_groups::Model {
+        name: name.clone(),
+        offset_km,
+    };
+
+    extension_groups::Entity::insert(extension_groups::ActiveModel::from(group.clone()))
+        .on_conflict(
+            sea_query::OnConflict::column(extension_groups::Column::Name)
+                .update_column(extension_groups::Column::OffsetKm)
+                .to_owned(),
+        )
+        .exec(&txn)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let affected: Vec<(String, String)> = postcode::Entity::find()
+        .select_only()
+        .column(postcode::Column::CountryCode)
+        .column(postcode::Column::Postcode)
+        .filter(postcode::Column::PostcodeExtensionDistanceGroup.eq(&name))
+        .filter(postcode::Column::ExtensionOffsetKm.is_null())
+        .into_tuple()
+        .all(&txn)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let affected: HashSet<PostcodeId> = affected.into_iter().map(PostcodeId::from).collect();
+
+    offsets_changed(&state, &affected, txn).await?;
+
+    serde_json::to_string(&group).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)
+}
+
+pub async fn delete(
+    Path(name): Path<String>,
+    State(AppState { db, .. }): State<AppState>,
+) -> Result<StatusCode, StatusCode> {
+    let deleted = extension_groups::Entity::delete_by_id(name)
+        .exec(&db)
+        .await
+        .map_err(map_write_err)?;
+
+    if deleted.rows_affected == 0 {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Moves the postcode to another extension group and sets or clears its own offset.
+pub async fn put_postcode(
+    Path((country_code, code)): Path<(String, String)>,
+    State(state): State<AppState>,
+    ValidJson(PostcodeBody {
+        extension_group,
+        offset_km,
+    }): ValidJson<PostcodeBody>,
+) -> Result<String, StatusCode> {
+    let id = PostcodeId::new(&country_code, &code);
+
+    let txn = state
+        .db
+        .begin()
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let mut postcode: postcode::ActiveModel =
+        postcode::Entity::find_by_id((id.country_code.clone(), id.postcode.clone()))
+            .one(&txn)
+            .await
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+            .ok_or(StatusCode::NOT_FOUND)?
+            .into();
+
+    postcode.postcode_extension_distance_group = ActiveValue::Set(extension_group);
+    postcode.extension_offset_km = ActiveValue::Set(offset_km);
+    let postcode = postcode.update(&txn).await.map_err(map_write_err)?;
+
+    offsets_changed(&state, &HashSet::from([id]), txn).await?;
+
+    serde_json::to_string(&postcode).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)
+}
+
+/// Recomputes the ranks of the postcodes with their new offsets and commits, then swaps in
+/// the reloaded postcodes so profile updates use the new offsets from now on.
+async fn offsets_changed(
+    state: &AppState,
+    affected: &HashSet<PostcodeId>,
+    txn: DatabaseTransaction,
+) -> Result<(), StatusCode> {
+    let postcodes = load_postcodes(&txn)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let changed: Vec<_> = postcodes
+        .iter()
+        .filter(|postcode| affected.contains(postcode.id()))
+        .collect();
+    let changed_postcodes = materialize_postcodes(&changed, &state.distances, &txn).await?;
+
+    state
+        .commit_postcodes(postcodes, txn)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    state.postcodes_changed(changed_postcodes);
+
+    Ok(())
+}
@@ -0,0 +1,62 @@
+use axum::{
+    async_trait,
+    body::Body,
+    extract::{FromRequest, FromRequestParts, Query},
+    http::{request::Parts, Request},
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::de::DeserializeOwned;
+
+use crate::utils::validation::Validate;
+
+use super::app_state::AppState;
+
+/// `Json` that only accepts bodies passing validation, otherwise responds with a 422
+pub struct ValidJson<T>(pub T);
+
+/// `Query` that only accepts parameters passing validation, otherwise responds with a 422
+pub struct ValidQuery<T>(pub T);
+
+#[async_trait]
+impl<T> FromRequest<AppState, Body> for ValidJson<T>
+where
+    T: DeserializeOwned + Validate,
+{
+    type Rejection = Response;
+
+    async fn from_request(req: Request<Body>, state: &AppState) -> Result<Self, Self::Rejection> {
+        let Json(value) = Json::<T>::from_request(req, state)
+            .await
+            .map_err(|rejection| rejection.into_response())?;
+
+        value
+            .validate(state)
+            .map_err(|errors| errors.into_response())?;
+
+        Ok(Self(value))
+    }
+}
+
+#[async_trait]
+impl<T> FromRequestParts<AppState> for ValidQuery<T>
+where
+    T: DeserializeOwned + Validate,
+{
+    type Rejection = Response;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &AppState,
+    ) -> Result<Self, Self::Rejection> {
+        let Query(value) = Query::<T>::from_request_parts(parts, state)
+            .await
+            .map_err(|rejection| rejection.into_response())?;
+
+        value
+            .validate(state)
+            .map_err(|errors| errors.into_response())?;
+
+        Ok(Self(value))
+    }
+}
@@ -0,0 +1,68 @@
+use axum::{
+    extract::{Path, Query, State},
+    headers::IfNoneMatch,
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    TypedHeader,
+};
+use sea_orm::EntityTrait;
+use serde::Deserialize;
+
+use crate::{
+    database::{filtered_ranks, profiles},
+    utils::postcode_utils::{PostcodeId, DEFAULT_COUNTRY},
+    utils::profile::CraftsmanDetails,
+};
+
+use super::app_state::AppState;
+
+#[derive(Deserialize)]
+pub struct ReqQuery {
+    /// include the distance to this postcode, e.g. the one that was searched for
+    postalcode: Option<String>,
+    /// ISO 3166-1 alpha-2 code, Germany if missing
+    country: Option<String>,
+}
+
+pub async fn handler(
+    Path(id): Path<i32>,
+    Query(ReqQuery {
+        postalcode,
+        country,
+    }): Query<ReqQuery>,
+    State(AppState { db, .. }): State<AppState>,
+    if_none_match: Option<TypedHeader<IfNoneMatch>>,
+) -> Result<Response, StatusCode> {
+    let profile: profiles::Model = profiles::Entity::find_by_id(id)
+        .one(&db)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    // clients pass this back in `If-Match` when patching the profile
+    let etag = profile.etag().ok_or(StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    // the distance can change without the profile, e.g. when postcodes are reloaded, so only
+    // the profile alone is answered with a 304
+    if let (Some(TypedHeader(if_none_match)), None) = (if_none_match, &postalcode) {
+        if !if_none_match.precondition_passes(&etag) {
+            return Ok((StatusCode::NOT_MODIFIED, TypedHeader(etag)).into_response());
+        }
+    }
+
+    let mut details: CraftsmanDetails = profile.into();
+
+    if let Some(postalcode) = postalcode {
+        let postcode = PostcodeId::new(country.as_deref().unwrap_or(DEFAULT_COUNTRY), &postalcode);
+
+        details.distance =
+            filtered_ranks::Entity::find_by_id((id, postcode.country_code, postcode.postcode))
+                .one(&db)
+                .await
+                .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+                .map(|rank| rank.distance);
+    }
+    let body = serde_json::to_string(&details).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok((TypedHeader(etag), body).into_response())
+}
@@ -1,26 +1,129 @@
 use crate::{
-    database::{filtered_ranks, profiles},
+    database::{filtered_ranks, profile_services, profiles},
+    utils::availability::{self, AvailabilityPenalty},
+    utils::geometry::{FeatureCollection, GEOJSON},
+    utils::postcode_utils::{PostcodeId, DEFAULT_COUNTRY},
     utils::profile::Craftsman,
 };
 use axum::{
-    extract::{Query, State},
-    http::StatusCode,
+    extract::State,
+    headers::{CacheControl, IfNoneMatch},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response as AxumResponse},
+    TypedHeader,
 };
+use chrono::{Days, NaiveDate, NaiveDateTime, Utc};
 use sea_orm::{
-    ColumnTrait, EntityTrait, JoinType, QueryFilter, QueryOrder, QuerySelect, RelationTrait,
+    ColumnTrait, DatabaseConnection, EntityTrait, JoinType, QueryFilter, QueryOrder, QuerySelect,
+    QueryTrait, RelationTrait, Select,
 };
+use sea_query::{Expr, Func, SimpleExpr};
 use serde::{Deserialize, Serialize};
+use std::sync::Arc;
 
 use crate::utils::profile;
+use crate::utils::validation::{self, Validate, ValidationErrors, Validator};
 
 use super::app_state::AppState;
+use super::extract::ValidQuery;
 
 const LIMIT: u64 = 20;
 
+/// number of top ranked craftsmen kept in the result cache per postcode
+const CACHED_RESULTS: u64 = 100;
+
 #[derive(Serialize, Deserialize, Debug)]
 pub struct ReqQuery {
     postalcode: String,
+    /// ISO 3166-1 alpha-2 code, Germany if missing
+    country: Option<String>,
     offset: Option<u64>,
+    service: Option<i32>,
+    /// only craftsmen with a free slot between these days, both inclusive
+    #[serde(rename = "availableFrom")]
+    available_from: Option<NaiveDate>,
+    #[serde(rename = "availableBy")]
+    available_by: Option<NaiveDate>,
+    /// in km, craftsmen further away are left out
+    #[serde(rename = "maxDistance")]
+    max_distance: Option<f64>,
+    /// minimum profile score
+    #[serde(rename = "minScore")]
+    min_score: Option<f64>,
+    #[serde(default)]
+    sort: Sort,
+    #[serde(default)]
+    format: Format,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Sort {
+    /// best ranked first
+    #[default]
+    Rank,
+    /// closest first, ties by rank
+    Distance,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum Format {
+    #[default]
+    Json,
+    /// FeatureCollection of the craftsmen as points, e.g. for GIS tools
+    GeoJson,
+}
+
+impl ReqQuery {
+    fn postcode_id(&self) -> PostcodeId {
+        let country = self.country.as_deref().unwrap_or(DEFAULT_COUNTRY);
+        PostcodeId::new(country, &self.postalcode)
+    }
+}
+
+impl Validate for ReqQuery {
+    fn validate(&self, state: &AppState) -> Result<(), ValidationErrors> {
+        let ordered = match (self.available_from, self.available_by) {
+            (Some(from), Some(by)) => from <= by,
+            _ => true,
+        };
+
+        Validator::new(state)
+            .field(
+                "postalcode",
+                &self.postcode_id(),
+                &[validation::known_postcode],
+            )
+            .check("availableBy", ordered, "must not be before availableFrom")
+            .optional(
+                "maxDistance",
+                &self.max_distance,
+                &[validation::finite, validation::positive],
+            )
+            .optional("minScore", &self.min_score, &[validation::score])
+            .finish()
+    }
+}
+
+/// Narrows down and orders the search. Searches without any of it are the ones cached.
+#[derive(Default)]
+struct Filters {
+    service: Option<i32>,
+    available: Option<SimpleExpr>,
+    max_distance: Option<f64>,
+    min_score: Option<f64>,
+    sort: Sort,
+}
+
+impl Filters {
+    fn is_empty(&self) -> bool {
+        self.service.is_none()
+            && self.available.is_none()
+            && self.max_distance.is_none()
+            && self.min_score.is_none()
+            && self.sort == Sort::Rank
+    }
 }
 
 #[derive(Serialize)]
@@ -29,30 +132,215 @@ pub struct Response {
 }
 
 pub async fn handler(
-    Query(ReqQuery { postalcode, offset }): Query<ReqQuery>,
-    State(AppState { db, .. }): State<AppState>,
-) -> Result<String, StatusCode> {
-    let postcode = postalcode
-        .parse::<i32>()
-        .map_err(|_| StatusCode::BAD_REQUEST)?;
+    ValidQuery(query): ValidQuery<ReqQuery>,
+    State(AppState {
+        db,
+        versions,
+        cache,
+        availability_penalty,
+        ..
+    }): State<AppState>,
+    if_none_match: Option<TypedHeader<IfNoneMatch>>,
+) -> Result<AxumResponse, StatusCode> {
+    let postcode = query.postcode_id();
+    let ReqQuery {
+        offset,
+        service,
+        available_from,
+        available_by,
+        max_distance,
+        min_score,
+        sort,
+        format,
+        ..
+    } = query;
+
+    let offset = offset.unwrap_or_default();
+    let version = versions.get(&postcode);
+
+    // the availability penalty and availability starting today depend on the current time,
+    // which the version doesn't cover
+    let time_dependent =
+        availability_penalty.is_enabled() || (available_by.is_some() && available_from.is_none());
+
+    // otherwise results only change when a profile serving this postcode is modified,
+    // so clients always revalidate but mostly get a 304 back
+    let etag = if time_dependent {
+        None
+    } else {
+        let etag = versions.etag(&postcode, version, offset);
+        Some(etag.ok_or(StatusCode::INTERNAL_SERVER_ERROR)?)
+    };
+    let cache_control = CacheControl::new().with_no_cache();
+
+    if let (Some(TypedHeader(if_none_match)), Some(etag)) = (if_none_match, &etag) {
+        if !if_none_match.precondition_passes(etag) {
+            return Ok((
+                StatusCode::NOT_MODIFIED,
+                TypedHeader(etag.clone()),
+                TypedHeader(cache_control),
+            )
+                .into_response());
+        }
+    }
+
+    let available = match (available_from, available_by) {
+        (None, None) => None,
+        (from, by) => {
+            let from = from.map_or_else(|| Utc::now().naive_utc(), start_of_day);
+            let until = by.and_then(|by| by.checked_add_days(Days::new(1)));
+            Some(availability::available(from, until.map(start_of_day)))
+        }
+    };
+
+    let filters = Filters {
+        service,
+        available,
+        max_distance,
+        min_score,
+        sort,
+    };
+
+    // only the unfiltered results are cached
+    let craftsmen = if filters.is_empty() && offset + LIMIT <= CACHED_RESULTS {
+        let top = match cache.get(&postcode, version) {
+            Some(top) => top,
+            None => {
+                let top = Arc::new(
+                    query_craftsmen(
+                        &db,
+                        &postcode,
+                        Filters::default(),
+                        availability_penalty,
+                        0,
+                        CACHED_RESULTS,
+                    )
+                    .await?,
+                );
+                cache.insert(postcode, version, top.clone());
+                top
+            }
+        };
+
+        top.iter()
+            .skip(offset as usize)
+            .take(LIMIT as usize)
+            .cloned()
+            .collect()
+    } else {
+        query_craftsmen(&db, &postcode, filters, availability_penalty, offset, LIMIT).await?
+    };
+
+    let response = match format {
+        Format::Json => {
+            let body = serde_json::to_string(&Response { craftsmen })
+                .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+            (etag.map(TypedHeader), TypedHeader(cache_control), body).into_response()
+        }
+        Format::GeoJson => {
+            let features = craftsmen.into_iter().map(Craftsman::into_feature).collect();
+            let body = serde_json::to_string(&FeatureCollection { features })
+                .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+            (
+                etag.map(TypedHeader),
+                TypedHeader(cache_control),
+                [(header::CONTENT_TYPE, GEOJSON)],
+                body,
+            )
+                .into_response()
+        }
+    };
+
+    Ok(response)
+}
+
+/// Profiles ranked for the postcode that are within their radius for the search, which is the
+/// one of the service if given.
+pub fn ranked_profiles(postcode: &PostcodeId, service: Option<i32>) -> Select<profiles::Entity> {
+    let required_distance = Expr::col((
+        filtered_ranks::Entity,
+        filtered_ranks::Column::RequiredDistance,
+    ));
+    let profile_distance = Expr::col((profiles::Entity, profiles::Column::MaxDrivingDistance));
 
     // TODO make the filter a subquery and then join with that (see if that does us any good)
-    let craftsmen: Vec<Craftsman> = profiles::Entity::find()
-        .column_as(filtered_ranks::Column::Rank, "rank")
-        .column_as(filtered_ranks::Column::Distance, "distance")
+    let query = profiles::Entity::find()
         .join(JoinType::LeftJoin, profiles::Relation::FilteredRanks.def())
-        .filter(filtered_ranks::Column::Postcode.eq(postcode))
-        .order_by_desc(filtered_ranks::Column::Rank)
+        .filter(filtered_ranks::Column::CountryCode.eq(&postcode.country_code))
+        .filter(filtered_ranks::Column::Postcode.eq(&postcode.postcode));
+
+    // ranks are materialized for the largest radius of each profile, so the radius that
+    // applies to this search has to be checked here (and is converted from meters to km)
+    match service {
+        None => query.filter(required_distance.lte(profile_distance.div(1000.0))),
+        Some(service) => query
+            .join(
+                JoinType::InnerJoin,
+                profiles::Relation::ProfileServices.def(),
+            )
+            .filter(profile_services::Column::ServiceId.eq(service))
+            .filter(
+                required_distance.lte(
+                    Expr::expr(Func::coalesce([
+                        Expr::col((
+                            profile_services::Entity,
+                            profile_services::Column::MaxDrivingDistance,
+                        ))
+                        .into(),
+                        profile_distance.into(),
+                    ]))
+                    .div(1000.0),
+                ),
+            ),
+    }
+}
+
+fn start_of_day(day: NaiveDate) -> NaiveDateTime {
+    day.and_hms_opt(0, 0, 0).unwrap_or_default()
+}
+
+async fn query_craftsmen(
+    db: &DatabaseConnection,
+    postcode: &PostcodeId,
+    filters: Filters,
+    penalty: AvailabilityPenalty,
+    offset: u64,
+    limit: u64,
+) -> Result<Vec<Craftsman>, StatusCode> {
+    // the penalty is applied on top of the materialized rank at query time, since it changes
+    // with the time of the search and not only with the profile
+    let rank = penalty.rank();
+
+    let query = ranked_profiles(postcode, filters.service)
+        .column_as(rank.clone(), "rank")
+        .column_as(filtered_ranks::Column::Distance, "distance")
+        .apply_if(filters.available, QueryFilter::filter)
+        .apply_if(filters.max_distance, |query, max_distance| {
+            query.filter(filtered_ranks::Column::Distance.lte(max_distance))
+        })
+        .apply_if(filters.min_score, |query, min_score| {
+            query.filter(profiles::Column::ProfileScore.gte(min_score))
+        });
+
+    let query = match filters.sort {
+        Sort::Rank => query.order_by_desc(rank),
+        Sort::Distance => query
+            .order_by_asc(filtered_ranks::Column::Distance)
+            .order_by_desc(rank),
+    };
+
+    let craftsmen: Vec<Craftsman> = query
         .offset(offset)
-        .limit(Some(LIMIT))
+        .limit(limit)
         .into_model::<profile::ProfileWithRank>()
-        .all(&db)
+        .all(db)
         .await
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
         .into_iter()
         .map(|profile| profile.into())
         .collect();
 
-    Ok(serde_json::to_string(&Response { craftsmen })
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?)
+    Ok(craftsmen)
 }
@@ -0,0 +1,18 @@
+use axum::{extract::State, http::StatusCode};
+use serde::Serialize;
+
+use crate::utils::result_cache::CacheStats;
+
+use super::app_state::AppState;
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Response {
+    result_cache: CacheStats,
+}
+
+pub async fn handler(State(AppState { cache, .. }): State<AppState>) -> Result<String, StatusCode> {
+    let result_cache = cache.stats();
+
+    serde_json::to_string(&Response { result_cache }).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)
+}
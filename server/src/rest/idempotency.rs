@@ -0,0 +1,79 @@
+use axum::{
+    body::{boxed, Body, Full},
+    extract::State,
+    http::{HeaderValue, Method, Request, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+
+use crate::utils::idempotency::{fingerprint, Lookup, Pending, StoredResponse};
+
+use super::app_state::AppState;
+
+const IDEMPOTENCY_KEY: &str = "idempotency-key";
+const REPLAYED: &str = "idempotent-replayed";
+
+/// Replays the stored response for POST/PATCH/DELETE requests that repeat an `Idempotency-Key`,
+/// so retried requests don't redo the work.
+pub async fn middleware(
+    State(AppState { idempotency, .. }): State<AppState>,
+    req: Request<Body>,
+    next: Next<Body>,
+) -> Response {
+    if ![Method::POST, Method::PATCH, Method::DELETE].contains(req.method()) {
+        return next.run(req).await;
+    }
+
+    let Some(key) = req
+        .headers()
+        .get(IDEMPOTENCY_KEY)
+        .and_then(|key| key.to_str().ok())
+    else {
+        return next.run(req).await;
+    };
+
+    // keys are only unique per client, so scope them to the endpoint they were used on
+    let key = format!("{} {} {}", req.method(), req.uri().path(), key);
+
+    let (parts, body) = req.into_parts();
+    let Ok(body) = hyper::body::to_bytes(body).await else {
+        return StatusCode::BAD_REQUEST.into_response();
+    };
+
+    match idempotency.begin(&key, fingerprint(&body)) {
+        Lookup::New => {}
+        Lookup::InFlight => return StatusCode::CONFLICT.into_response(),
+        Lookup::Mismatch => return StatusCode::UNPROCESSABLE_ENTITY.into_response(),
+        Lookup::Replay(StoredResponse {
+            status,
+            mut headers,
+            body,
+        }) => {
+            headers.insert(REPLAYED, HeaderValue::from_static("true"));
+            return (status, headers, body).into_response();
+        }
+    }
+
+    // dropping it without completing forgets the key, also when the request is cancelled
+    let pending = Pending::new(&idempotency, key);
+
+    let res = next.run(Request::from_parts(parts, Body::from(body))).await;
+
+    // server errors are not final, the client should be able to retry those
+    if res.status().is_server_error() {
+        return res;
+    }
+
+    let (parts, body) = res.into_parts();
+    let Ok(body) = hyper::body::to_bytes(body).await else {
+        return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+    };
+
+    pending.complete(StoredResponse {
+        status: parts.status,
+        headers: parts.headers.clone(),
+        body: body.clone(),
+    });
+
+    Response::from_parts(parts, boxed(Full::from(body)))
+}
@@ -0,0 +1,469 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+};
+use sea_orm::{
+    ActiveValue, ColumnTrait, ConnectionTrait, DatabaseTransaction, EntityTrait, Iterable,
+    QueryFilter, QueryOrder, QuerySelect, TransactionTrait,
+};
+use sea_query::Expr;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    database::{
+        filtered_ranks, job_offers, job_requests, profiles,
+        sea_orm_active_enums::{JobStatus, OfferStatus},
+        services,
+    },
+    utils::job::OFFERS_PER_ROUND,
+    utils::postcode_utils::{PostcodeId, DEFAULT_COUNTRY},
+    utils::validation::{self, Validate, ValidationErrors, Validator},
+};
+
+use super::app_state::AppState;
+use super::extract::ValidJson;
+use super::get_craftsmen::ranked_profiles;
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NewJob {
+    postalcode: String,
+    country: Option<String>,
+    service: i32,
+    customer_name: String,
+    customer_contact: String,
+    #[serde(default)]
+    description: String,
+}
+
+impl NewJob {
+    fn postcode_id(&self) -> PostcodeId {
+        let country = self.country.as_deref().unwrap_or(DEFAULT_COUNTRY);
+        PostcodeId::new(country, &self.postalcode)
+    }
+}
+
+impl Validate for NewJob {
+    fn validate(&self, state: &AppState) -> Result<(), ValidationErrors> {
+        Validator::new(state)
+            .field(
+                "postalcode",
+                &self.postcode_id(),
+                &[validation::known_postcode],
+            )
+            .field(
+                "customerName",
+                self.customer_name.as_str(),
+                &[validation::not_blank],
+            )
+            .field(
+                "customerContact",
+                self.customer_contact.as_str(),
+                &[validation::not_blank],
+            )
+            .finish()
+    }
+}
+
+#[derive(Serialize)]
+pub struct JobDetails {
+    #[serde(flatten)]
+    job: job_requests::Model,
+    offers: Vec<job_offers::Model>,
+}
+
+#[derive(Serialize)]
+pub struct Response {
+    jobs: Vec<job_requests::Model>,
+}
+
+/// Offers the job to the best ranked craftsmen it wasn't offered to yet. Returns how many
+/// craftsmen got an offer, none if everybody in range already had one.
+async fn offer_next_round<C: ConnectionTrait>(
+    job: &job_requests::Model,
+    txn: &C,
+) -> Result<usize, StatusCode> {
+    let offered: Vec<i32> = job_offers::Entity::find()
+        .select_only()
+        .column(job_offers::Column::ProfileId)
+        .filter(job_offers::Column::JobRequestId.eq(job.id))
+        .into_tuple()
+        .all(txn)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let postcode = PostcodeId::new(&job.country_code, &job.postcode);
+    let profile_ids: Vec<i32> = ranked_profiles(&postcode, Some(job.service_id))
+        .select_only()
+        .column(profiles::Column::Id)
+        .filter(profiles::Column::Id.is_not_in(offered))
+        .order_by_desc(filtered_ranks::Column::Rank)
+        .limit(OFFERS_PER_ROUND)
+        .into_tuple()
+        .all(txn)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    if profile_ids.is_empty() {
+        return Ok(0);
+    }
+
+    let offers = profile_ids
+        .iter()
+        .map(|&profile_id| job_offers::ActiveModel {
+            job_request_id: ActiveValue::Set(job.id),
+            profile_id: ActiveValue::Set(profile_id),
+            ..Default::default()
+        });
+
+    job_offers::Entity::insert_many(offers)
+        .exec(txn)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(profile_ids.len())
+}
+
+/// Moves the job to the next state, 409 if the state machine doesn't allow it.
+async fn transition<C: ConnectionTrait>(
+    job: job_requests::Model,
+    next: JobStatus,
+    profile_id: Option<i32>,
+    txn: &C,
+) -> Result<job_requests::Model, StatusCode> {
+    if job.status == next {
+        return Ok(job);
+    }
+    if !job.status.can_become(next) {
+        return Err(StatusCode::CONFLICT);
+    }
+
+    let id = job.id;
+    let mut job: job_requests::ActiveModel = job.into();
+    job.status = ActiveValue::Set(next);
+    if profile_id.is_some() {
+        job.profile_id = ActiveValue::Set(profile_id);
+    }
+
+    job_requests::Entity::update_many()
+        .set(job)
+        .col_expr(
+            job_requests::Column::UpdatedAt,
+            Expr::current_timestamp().into(),
+        )
+        .filter(job_requests::Column::Id.eq(id))
+        .exec_with_returning(txn)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .pop()
+        .ok_or(StatusCode::INTERNAL_SERVER_ERROR)
+}
+
+/// Answers the pending offers of the job, all of them or only the one of a craftsman. Offers
+/// that can't move to the status anymore are left as they are.
+async fn answer_offers<C: ConnectionTrait>(
+    job_id: i32,
+    profile_id: Option<i32>,
+    status: OfferStatus,
+    txn: &C,
+) -> Result<Vec<job_offers::Model>, StatusCode> {
+    let mut query = job_offers::Entity::update_many()
+        .set(job_offers::ActiveModel {
+            status: ActiveValue::Set(status),
+            ..Default::default()
+        })
+        .filter(job_offers::Column::JobRequestId.eq(job_id))
+        .filter(
+            job_offers::Column::Status
+                .is_in(OfferStatus::iter().filter(|current| current.can_become(status))),
+        );
+
+    if let Some(profile_id) = profile_id {
+        query = query.filter(job_offers::Column::ProfileId.eq(profile_id));
+    }
+
+    query
+        .exec_with_returning(txn)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)
+}
+
+async fn lock_job(id: i32, txn: &DatabaseTransaction) -> Result<job_requests::Model, StatusCode> {
+    job_requests::Entity::find_by_id(id)
+        .lock_exclusive()
+        .one(txn)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::NOT_FOUND)
+}
+
+async fn details<C: ConnectionTrait>(
+    job: job_requests::Model,
+    db: &C,
+) -> Result<String, StatusCode> {
+    let offers = job_offers::Entity::find()
+        .filter(job_offers::Column::JobRequestId.eq(job.id))
+        .order_by_asc(job_offers::Column::CreatedAt)
+        .order_by_asc(job_offers::Column::ProfileId)
+        .all(db)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    serde_json::to_string(&JobDetails { job, offers })
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)
+}
+
+/// Creates the job and offers it to the best ranked craftsmen for the postcode and service.
+/// Jobs nobody is in range for stay open.
+pub async fn create(
+    State(AppState { db, .. }): State<AppState>,
+    ValidJson(new_job): ValidJson<NewJob>,
+) -> Result<(StatusCode, String), StatusCode> {
+    let PostcodeId {
+        country_code,
+        postcode,
+    } = new_job.postcode_id();
+    let NewJob {
+        service,
+        customer_name,
+        customer_contact,
+        description,
+        ..
+    } = new_job;
+
+    let txn = db
+        .begin()
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    services::Entity::find_by_id(service)
+        .one(&txn)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::UNPROCESSABLE_ENTITY)?;
+
+    let job = job_requests::Entity::insert(job_requests::ActiveModel {
+        country_code: ActiveValue::Set(country_code),
+        postcode: ActiveValue::Set(postcode),
+        service_id: ActiveValue::Set(service),
+        customer_name: ActiveValue::Set(customer_name.trim().to_owned()),
+        customer_contact: ActiveValue::Set(customer_contact.trim().to_owned()),
+        description: ActiveValue::Set(description),
+        ..Default::default()
+    })
+    .exec_with_returning(&txn)
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let job = if offer_next_round(&job, &txn).await? > 0 {
+        transition(job, JobStatus::Offered, None, &txn).await?
+    } else {
+        job
+    };
+
+    let body = details(job, &txn).await?;
+
+    txn.commit()
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok((StatusCode::CREATED, body))
+}
+
+pub async fn get(
+    Path(id): Path<i32>,
+    State(AppState { db, .. }): State<AppState>,
+) -> Result<String, StatusCode> {
+    let job = job_requests::Entity::find_by_id(id)
+        .one(&db)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    details(job, &db).await
+}
+
+/// jobs a craftsman was offered and didn't answer yet, oldest first
+pub async fn offered_to(
+    Path(id): Path<i32>,
+    State(AppState { db, .. }): State<AppState>,
+) -> Result<String, StatusCode> {
+    profiles::Entity::find_by_id(id)
+        .one(&db)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    let jobs = job_requests::Entity::find()
+        .inner_join(job_offers::Entity)
+        .filter(job_offers::Column::ProfileId.eq(id))
+        .filter(job_offers::Column::Status.eq(OfferStatus::Offered))
+        .order_by_asc(job_requests::Column::CreatedAt)
+        .all(&db)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    serde_json::to_string(&Response { jobs }).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)
+}
+
+/// Offers an open job again, e.g. once new craftsmen are in range.
+pub async fn reoffer(
+    Path(id): Path<i32>,
+    State(AppState { db, .. }): State<AppState>,
+) -> Result<String, StatusCode> {
+    let txn = db
+        .begin()
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let job = lock_job(id, &txn).await?;
+    if job.status != JobStatus::Open {
+        return Err(StatusCode::CONFLICT);
+    }
+
+    if offer_next_round(&job, &txn).await? == 0 {
+        return Err(StatusCode::CONFLICT);
+    }
+    let job = transition(job, JobStatus::Offered, None, &txn).await?;
+
+    let body = details(job, &txn).await?;
+
+    txn.commit()
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(body)
+}
+
+/// The craftsman takes the job, the offers of everybody else are withdrawn.
+pub async fn accept(
+    Path((id, profile_id)): Path<(i32, i32)>,
+    State(AppState { db, .. }): State<AppState>,
+) -> Result<String, StatusCode> {
+    let txn = db
+        .begin()
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let job = lock_job(id, &txn).await?;
+    if !job.status.can_become(JobStatus::Accepted) {
+        return Err(StatusCode::CONFLICT);
+    }
+
+    let answered = answer_offers(id, Some(profile_id), OfferStatus::Accepted, &txn).await?;
+    if answered.is_empty() {
+        return Err(offer_missing(id, profile_id, &txn).await?);
+    }
+    answer_offers(id, None, OfferStatus::Withdrawn, &txn).await?;
+
+    let job = transition(job, JobStatus::Accepted, Some(profile_id), &txn).await?;
+
+    let body = details(job, &txn).await?;
+
+    txn.commit()
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(body)
+}
+
+/// The craftsman passes on the job. Once every offer is declined the job moves on to the next
+/// best ranked craftsmen, or is open again if there are none.
+pub async fn decline(
+    Path((id, profile_id)): Path<(i32, i32)>,
+    State(AppState { db, .. }): State<AppState>,
+) -> Result<String, StatusCode> {
+    let txn = db
+        .begin()
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let job = lock_job(id, &txn).await?;
+
+    let answered = answer_offers(id, Some(profile_id), OfferStatus::Declined, &txn).await?;
+    if answered.is_empty() {
+        return Err(offer_missing(id, profile_id, &txn).await?);
+    }
+
+    let pending = job_offers::Entity::find()
+        .filter(job_offers::Column::JobRequestId.eq(id))
+        .filter(job_offers::Column::Status.eq(OfferStatus::Offered))
+        .one(&txn)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let job = if pending.is_none() && offer_next_round(&job, &txn).await? == 0 {
+        transition(job, JobStatus::Open, None, &txn).await?
+    } else {
+        job
+    };
+
+    let body = details(job, &txn).await?;
+
+    txn.commit()
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(body)
+}
+
+/// 404 if the job was never offered to the craftsman, 409 if the offer was already answered
+async fn offer_missing<C: ConnectionTrait>(
+    id: i32,
+    profile_id: i32,
+    txn: &C,
+) -> Result<StatusCode, StatusCode> {
+    let offer = job_offers::Entity::find_by_id((id, profile_id))
+        .one(txn)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(match offer {
+        Some(_) => StatusCode::CONFLICT,
+        None => StatusCode::NOT_FOUND,
+    })
+}
+
+pub async fn complete(
+    Path(id): Path<i32>,
+    State(AppState { db, .. }): State<AppState>,
+) -> Result<String, StatusCode> {
+    let txn = db
+        .begin()
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let job = lock_job(id, &txn).await?;
+    let job = transition(job, JobStatus::Completed, None, &txn).await?;
+
+    let body = details(job, &txn).await?;
+
+    txn.commit()
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(body)
+}
+
+/// Cancels the job and withdraws the offers nobody answered yet.
+pub async fn cancel(
+    Path(id): Path<i32>,
+    State(AppState { db, .. }): State<AppState>,
+) -> Result<String, StatusCode> {
+    let txn = db
+        .begin()
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let job = lock_job(id, &txn).await?;
+    let job = transition(job, JobStatus::Cancelled, None, &txn).await?;
+    answer_offers(id, None, OfferStatus::Withdrawn, &txn).await?;
+
+    let body = details(job, &txn).await?;
+
+    txn.commit()
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(body)
+}
@@ -1,3 +1,23 @@
 pub mod app_state;
+pub mod availability;
+pub mod coverage;
+pub mod coverage_gaps;
+pub mod extension_groups;
+pub mod extract;
+pub mod get_craftsman;
 pub mod get_craftsmen;
+pub mod get_metrics;
+pub mod idempotency;
+pub mod jobs;
 pub mod patch_craftsmen;
+pub mod patch_craftsmen_batch;
+pub mod postcode_boundaries;
+pub mod postcodes;
+pub mod profile_login;
+pub mod profile_media;
+pub mod profile_services;
+pub mod reload_postcodes;
+pub mod reviews;
+pub mod service_area;
+pub mod services;
+pub mod tiles;
@@ -1,31 +1,68 @@
 use axum::{
     extract::{Path, State},
+    headers::{authorization::Bearer, Authorization, IfMatch},
     http::StatusCode,
-    Json,
+    response::{IntoResponse, Response},
+    TypedHeader,
 };
 use geoutils::Location;
 use sea_orm::{
-    ActiveModelTrait, ActiveValue, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter,
-    QueryOrder, TransactionTrait,
+    ActiveValue, ColumnTrait, ConnectionTrait, EntityTrait, QueryFilter, QueryOrder, QuerySelect,
+    TransactionTrait,
 };
+use sea_query::Expr;
 use serde::{Deserialize, Serialize};
-use std::sync::Arc;
+use std::collections::HashMap;
+use tokio::time::Instant;
 
 use crate::{
-    database::{filtered_ranks, profiles},
-    utils::postcode_utils::{PatchFilters, Postcode},
+    database::{filtered_ranks, profile_services, profiles},
+    utils::auth::authorize,
+    utils::postcode_utils::{PatchFilters, Postcode, PostcodeId},
     utils::ranking::calc_rank,
+    utils::routing::Distances,
     utils::scoring,
+    utils::validation::{self, Validate, ValidationErrors, Validator},
 };
 
 use super::app_state::AppState;
+use super::extract::ValidJson;
+
+/// rows per statement when rewriting ranks in bulk, well below the bind parameter limit
+pub const CHUNK_SIZE: usize = 1000;
 
 #[derive(Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ReqBody {
-    max_driving_distance: Option<f64>,
-    profile_picture_score: Option<f64>,
-    profile_description_score: Option<f64>,
+    pub max_driving_distance: Option<f64>,
+    pub profile_picture_score: Option<f64>,
+    pub profile_description_score: Option<f64>,
+}
+
+impl Validate for ReqBody {
+    fn validate(&self, state: &AppState) -> Result<(), ValidationErrors> {
+        Validator::new(state)
+            .optional(
+                "maxDrivingDistance",
+                &self.max_driving_distance,
+                &[
+                    validation::finite,
+                    validation::positive,
+                    validation::driving_distance,
+                ],
+            )
+            .optional(
+                "profilePictureScore",
+                &self.profile_picture_score,
+                &[validation::finite, validation::score],
+            )
+            .optional(
+                "profileDescriptionScore",
+                &self.profile_description_score,
+                &[validation::finite, validation::score],
+            )
+            .finish()
+    }
 }
 
 #[derive(Serialize, Deserialize)]
@@ -42,37 +79,47 @@ pub struct QueryResult {
     pub updated: Updated,
 }
 
-async fn update_score_and_ranks(
+/// Writes the changed columns of the profile, but only if its version is still the one it was
+/// read at. Returns the stored profile with the bumped version, or 412 if someone else got there
+/// first.
+pub async fn update_profile<C: ConnectionTrait>(
+    profile: profiles::ActiveModel,
+    id: i32,
+    version: i32,
+    db: &C,
+) -> Result<profiles::Model, StatusCode> {
+    profiles::Entity::update_many()
+        .set(profile)
+        .col_expr(
+            profiles::Column::Version,
+            Expr::col(profiles::Column::Version).add(1),
+        )
+        .filter(profiles::Column::Id.eq(id))
+        .filter(profiles::Column::Version.eq(version))
+        .exec_with_returning(db)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .pop()
+        .ok_or(StatusCode::PRECONDITION_FAILED)
+}
+
+async fn update_score_and_ranks<C: ConnectionTrait>(
     profile: profiles::Model,
     pic_score: Option<f64>,
     desc_score: Option<f64>,
-    db: DatabaseConnection,
-) -> Result<String, StatusCode> {
+    txn: &C,
+) -> Result<(profiles::Model, Vec<PostcodeId>), StatusCode> {
     // no max distance was given, at least one score is expected
     let new_score = scoring::calc_score_from_options(
         pic_score,
         desc_score,
         profile.profile_picture_score,
         profile.profile_description_score,
+        profile.review_score,
     )
     .ok_or(StatusCode::BAD_REQUEST)?;
 
-    // distance doesn't change, only rank, so query all in preperation for update
-    let ranks: Vec<filtered_ranks::ActiveModel> = filtered_ranks::Entity::find()
-        .filter(filtered_ranks::Column::ProfileId.eq(profile.id))
-        .order_by_asc(filtered_ranks::Column::Distance)
-        .all(&db)
-        .await
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
-        .into_iter()
-        .map(|filter| {
-            let dist = filter.distance;
-            let mut model: filtered_ranks::ActiveModel = filter.into();
-            model.rank = ActiveValue::Set(calc_rank(dist, new_score));
-            model
-        })
-        .collect();
-
+    let (id, version) = (profile.id, profile.version);
     let mut profile: profiles::ActiveModel = profile.into();
 
     // update all values that were changed
@@ -81,69 +128,104 @@ async fn update_score_and_ranks(
     }
 
     if let Some(desc_score) = desc_score {
-        profile.profile_picture_score = ActiveValue::Set(desc_score);
+        profile.profile_description_score = ActiveValue::Set(desc_score);
     }
 
     profile.profile_score = ActiveValue::Set(new_score);
 
-    // perform updates inside of transaction
-    let txn = db
-        .begin()
+    // the profile goes first, its row lock keeps concurrent updates from touching the ranks
+    let profile = update_profile(profile, id, version, txn).await?;
+    let changed_postcodes = rerank(id, new_score, txn).await?;
+
+    Ok((profile, changed_postcodes))
+}
+
+/// Sets a new review score for the profile, read with a lock held by the transaction,
+/// and updates its ranks accordingly.
+pub async fn update_review_score<C: ConnectionTrait>(
+    profile: profiles::Model,
+    review_score: Option<f64>,
+    txn: &C,
+) -> Result<(profiles::Model, Vec<PostcodeId>), StatusCode> {
+    let new_score = scoring::calc_score(
+        profile.profile_picture_score,
+        profile.profile_description_score,
+        review_score,
+    );
+
+    let (id, version) = (profile.id, profile.version);
+    let mut profile: profiles::ActiveModel = profile.into();
+
+    profile.review_score = ActiveValue::Set(review_score);
+    profile.profile_score = ActiveValue::Set(new_score);
+
+    let profile = update_profile(profile, id, version, txn).await?;
+    let changed_postcodes = rerank(id, new_score, txn).await?;
+
+    Ok((profile, changed_postcodes))
+}
+
+/// Recomputes the ranks of the profile for a new score. Returns the postcodes of the ranks.
+async fn rerank<C: ConnectionTrait>(
+    id: i32,
+    new_score: f64,
+    txn: &C,
+) -> Result<Vec<PostcodeId>, StatusCode> {
+    // distance doesn't change, only rank, so query all in preperation for update
+    let ranks: Vec<filtered_ranks::Model> = filtered_ranks::Entity::find()
+        .filter(filtered_ranks::Column::ProfileId.eq(id))
+        .order_by_asc(filtered_ranks::Column::Distance)
+        .all(txn)
         .await
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
+    let changed_postcodes: Vec<PostcodeId> = ranks.iter().map(|rank| rank.postcode_id()).collect();
+
+    let ranks: Vec<filtered_ranks::ActiveModel> = ranks
+        .into_iter()
+        .map(|filter| {
+            let dist = filter.distance;
+            let mut model: filtered_ranks::ActiveModel = filter.into();
+            model.rank = ActiveValue::Set(calc_rank(dist, new_score));
+            model
+        })
+        .collect();
+
     filtered_ranks::Entity::insert_many(ranks)
         .on_empty_do_nothing()
         .on_conflict(
             sea_query::OnConflict::columns([
                 filtered_ranks::Column::ProfileId,
+                filtered_ranks::Column::CountryCode,
                 filtered_ranks::Column::Postcode,
             ])
             .update_columns([filtered_ranks::Column::Rank])
             .to_owned(),
         )
-        .exec(&txn)
+        .exec(txn)
         .await
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
-    let profile = profile
-        .update(&txn)
-        .await
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-
-    txn.commit()
-        .await
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-
-    let query_result: QueryResult = profile.into();
-
-    Ok(serde_json::to_string(&query_result).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?)
+    Ok(changed_postcodes)
 }
 
-async fn update_distances(
+async fn update_distances<C: ConnectionTrait>(
     profile: profiles::Model,
     pic_score: Option<f64>,
     desc_score: Option<f64>,
     max_driving_distance: f64,
-    postcodes: Arc<Vec<Postcode>>,
-    db: DatabaseConnection,
-) -> Result<String, StatusCode> {
-    let id = profile.id;
+    state: &AppState,
+    txn: &C,
+) -> Result<(profiles::Model, Vec<PostcodeId>), StatusCode> {
+    let (id, version) = (profile.id, profile.version);
     let new_score = scoring::calc_score_from_options(
         pic_score,
         desc_score,
         profile.profile_picture_score,
         profile.profile_description_score,
+        profile.review_score,
     );
 
-    let patch = PatchFilters {
-        profile_id: profile.id,
-        // XXX this converts the meters to km, please excuse the magic number
-        max_driving_distance: max_driving_distance / 1000.0,
-        profile_score: new_score.unwrap_or(profile.profile_score),
-        loc: Location::new(profile.lat, profile.lon),
-    };
-
     let mut profile: profiles::ActiveModel = profile.into();
 
     // update all values that were changed
@@ -152,7 +234,7 @@ async fn update_distances(
     }
 
     if let Some(desc_score) = desc_score {
-        profile.profile_picture_score = ActiveValue::Set(desc_score);
+        profile.profile_description_score = ActiveValue::Set(desc_score);
     }
 
     if let Some(new_score) = new_score {
@@ -161,21 +243,140 @@ async fn update_distances(
 
     profile.max_driving_distance = ActiveValue::Set(max_driving_distance);
 
-    let filters: Vec<filtered_ranks::ActiveModel> = postcodes
+    let profile = update_profile(profile, id, version, txn).await?;
+    // only read with the row lock held, a rebuild of the postcodes that held it before has
+    // swapped in its postcodes by then
+    let postcodes = state.postcodes();
+    let changed_postcodes = materialize_ranks(&profile, &postcodes, &state.distances, txn).await?;
+
+    Ok((profile, changed_postcodes))
+}
+
+/// postcodes the profile currently shows up for
+pub async fn ranked_postcodes<C: ConnectionTrait>(
+    profile_id: i32,
+    txn: &C,
+) -> Result<Vec<PostcodeId>, StatusCode> {
+    let postcodes: Vec<(String, String)> = filtered_ranks::Entity::find()
+        .select_only()
+        .column(filtered_ranks::Column::CountryCode)
+        .column(filtered_ranks::Column::Postcode)
+        .filter(filtered_ranks::Column::ProfileId.eq(profile_id))
+        .into_tuple()
+        .all(txn)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(postcodes.into_iter().map(PostcodeId::from).collect())
+}
+
+/// filters for the service area of the profile, or the largest of its radii, which might be
+/// the one of a service
+pub fn patch_filters(
+    profile: &profiles::Model,
+    service_distance: Option<f64>,
+    distances: &Distances,
+) -> PatchFilters {
+    let max_driving_distance = service_distance.map_or(profile.max_driving_distance, |distance| {
+        distance.max(profile.max_driving_distance)
+    });
+
+    PatchFilters {
+        profile_id: profile.id,
+        // XXX this converts the meters to km, please excuse the magic number
+        max_driving_distance: max_driving_distance / 1000.0,
+        profile_score: profile.profile_score,
+        loc: Location::new(profile.lat, profile.lon),
+        metric: distances.metric,
+        coverage: distances.coverage,
+        // checked before it was stored
+        service_area: profile
+            .service_area
+            .clone()
+            .and_then(|area| serde_json::from_value(area).ok()),
+    }
+}
+
+/// Ranks of the profile for the postcodes within its radius. Distances are taken from the
+/// routing backend if there is one, falling back to the straight line for postcodes it
+/// couldn't answer for in time. Postcodes it knows no route to are left out.
+async fn profile_ranks<C: ConnectionTrait>(
+    profile: &profiles::Model,
+    patch: &PatchFilters,
+    postcodes: &[&Postcode],
+    distances: &Distances,
+    deadline: Instant,
+    txn: &C,
+) -> Result<Vec<filtered_ranks::Model>, StatusCode> {
+    // roads are never shorter than the straight line, so only postcodes within reach of it
+    // are worth asking the backend for
+    let candidates: Vec<(&Postcode, filtered_ranks::Model)> = postcodes
         .iter()
-        .filter_map(|postcode| postcode.get_model_opt(&patch).map(|model| model.into()))
+        .filter_map(|postcode| Some((*postcode, postcode.get_model_opt(patch)?)))
         .collect();
 
-    let txn = db
-        .begin()
+    let within_reach: Vec<&Postcode> = candidates.iter().map(|(postcode, _)| *postcode).collect();
+    let driving_distances = distances
+        .driving_distances(profile, &within_reach, deadline, txn)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(candidates
+        .into_iter()
+        .filter_map(
+            |(postcode, straight)| match driving_distances.get(postcode.id()) {
+                Some(Some(dist)) => postcode.get_model_with_distance(patch, *dist),
+                Some(None) => None,
+                None => Some(straight),
+            },
+        )
+        .collect())
+}
+
+/// Recomputes all `filtered_ranks` of the stored profile, for its service area or the largest
+/// of its radii.
+/// Returns the postcodes the profile was dropped from or added to.
+pub async fn materialize_ranks<C: ConnectionTrait>(
+    profile: &profiles::Model,
+    postcodes: &[Postcode],
+    distances: &Distances,
+    txn: &C,
+) -> Result<Vec<PostcodeId>, StatusCode> {
+    let id = profile.id;
+
+    // craftsmen might travel further for some of their services
+    let service_distances: Vec<Option<f64>> = profile_services::Entity::find()
+        .select_only()
+        .column(profile_services::Column::MaxDrivingDistance)
+        .filter(profile_services::Column::ProfileId.eq(id))
+        .into_tuple()
+        .all(txn)
         .await
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
+    let patch = patch_filters(
+        profile,
+        service_distances.into_iter().flatten().reduce(f64::max),
+        distances,
+    );
+
+    let postcodes: Vec<&Postcode> = postcodes.iter().collect();
+    let deadline = distances.routing_deadline();
+    let filters = profile_ranks(profile, &patch, &postcodes, distances, deadline, txn).await?;
+
+    // postcodes the profile is dropped from change as well as the ones it is added to
+    let mut changed_postcodes = ranked_postcodes(id, txn).await?;
+
+    changed_postcodes.extend(filters.iter().map(|filter| filter.postcode_id()));
+
+    let filters: Vec<filtered_ranks::ActiveModel> =
+        filters.into_iter().map(|filter| filter.into()).collect();
+
     // this is a classic Hackathon solution - we should really update existing fields,
     // but it's 2am and we are operating on 3h of sleep...
     filtered_ranks::Entity::delete_many()
         .filter(filtered_ranks::Column::ProfileId.eq(id))
-        .exec(&txn)
+        .exec(txn)
         .await
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
@@ -184,66 +385,179 @@ async fn update_distances(
         .on_conflict(
             sea_query::OnConflict::columns([
                 filtered_ranks::Column::ProfileId,
+                filtered_ranks::Column::CountryCode,
                 filtered_ranks::Column::Postcode,
             ])
             .update_columns([filtered_ranks::Column::Rank])
             .to_owned(),
         )
-        .exec(&txn)
+        .exec(txn)
         .await
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
-    let profile = profile
-        .update(&txn)
+    Ok(changed_postcodes)
+}
+
+/// Recomputes the `filtered_ranks` of the given postcodes for all profiles, e.g. after their
+/// extension offset changed. Returns the postcodes.
+pub async fn materialize_postcodes<C: ConnectionTrait>(
+    postcodes: &[&Postcode],
+    distances: &Distances,
+    txn: &C,
+) -> Result<Vec<PostcodeId>, StatusCode> {
+    let ids: Vec<PostcodeId> = postcodes
+        .iter()
+        .map(|postcode| postcode.id().clone())
+        .collect();
+    if ids.is_empty() {
+        return Ok(ids);
+    }
+
+    // locked, so profiles updated meanwhile aren't materialized with their old radius or score,
+    // and in order of their id to not deadlock with other rebuilds
+    let profiles = profiles::Entity::find()
+        .order_by_asc(profiles::Column::Id)
+        .lock_shared()
+        .all(txn)
         .await
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
-    txn.commit()
+    // read after the lock, changes of the services lock the profile too
+    let service_distances: HashMap<i32, f64> = profile_services::Entity::find()
+        .select_only()
+        .column(profile_services::Column::ProfileId)
+        .column_as(profile_services::Column::MaxDrivingDistance.max(), "max")
+        .group_by(profile_services::Column::ProfileId)
+        .into_tuple::<(i32, Option<f64>)>()
+        .all(txn)
         .await
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .into_iter()
+        .filter_map(|(id, distance)| Some((id, distance?)))
+        .collect();
 
-    let query_result: QueryResult = profile.into();
+    // shared by all profiles, the backend is only asked for the first ones if it's slow
+    let deadline = distances.routing_deadline();
+    let mut filters: Vec<filtered_ranks::Model> = Vec::new();
+    for profile in &profiles {
+        let patch = patch_filters(
+            profile,
+            service_distances.get(&profile.id).copied(),
+            distances,
+        );
+        filters.extend(profile_ranks(profile, &patch, postcodes, distances, deadline, txn).await?);
+    }
+
+    for chunk in ids.chunks(CHUNK_SIZE) {
+        let chunk = chunk
+            .iter()
+            .map(|id| (id.country_code.clone(), id.postcode.clone()));
+
+        filtered_ranks::Entity::delete_many()
+            .filter(
+                Expr::tuple([
+                    Expr::col(filtered_ranks::Column::CountryCode).into(),
+                    Expr::col(filtered_ranks::Column::Postcode).into(),
+                ])
+                .in_tuples(chunk),
+            )
+            .exec(txn)
+            .await
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    }
 
-    Ok(serde_json::to_string(&query_result).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?)
+    for chunk in filters.chunks(CHUNK_SIZE) {
+        filtered_ranks::Entity::insert_many(
+            chunk.iter().cloned().map(filtered_ranks::ActiveModel::from),
+        )
+        .exec(txn)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    }
+
+    Ok(ids)
 }
 
-pub async fn handler(
-    Path(id): Path<i32>,
-    State(AppState { db, postcodes }): State<AppState>,
-    Json(input): Json<ReqBody>,
-) -> Result<String, StatusCode> {
+/// Applies the update to the profile and its ranks inside of the given transaction.
+/// Returns the stored profile and the postcodes whose ranks changed.
+pub async fn apply_update<C: ConnectionTrait>(
+    profile: profiles::Model,
+    input: ReqBody,
+    state: &AppState,
+    txn: &C,
+) -> Result<(profiles::Model, Vec<PostcodeId>), StatusCode> {
     let ReqBody {
         max_driving_distance,
         profile_picture_score,
         profile_description_score,
     } = input;
 
-    let profile: profiles::Model = profiles::Entity::find_by_id(id)
-        .one(&db)
-        .await
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
-        .ok_or(StatusCode::NOT_FOUND)?;
-
     match max_driving_distance {
         Some(distance) => {
-            return update_distances(
+            update_distances(
                 profile,
                 profile_picture_score,
                 profile_description_score,
                 distance,
-                postcodes,
-                db,
+                state,
+                txn,
             )
             .await
         }
         None => {
-            return update_score_and_ranks(
+            update_score_and_ranks(
                 profile,
                 profile_picture_score,
                 profile_description_score,
-                db,
+                txn,
             )
             .await
         }
     }
 }
+
+/// Only the craftsman can update their profile, see `utils::auth`.
+pub async fn handler(
+    Path(id): Path<i32>,
+    State(state): State<AppState>,
+    auth: Option<TypedHeader<Authorization<Bearer>>>,
+    if_match: Option<TypedHeader<IfMatch>>,
+    ValidJson(input): ValidJson<ReqBody>,
+) -> Result<Response, StatusCode> {
+    authorize(id, auth, &state.db).await?;
+
+    let profile: profiles::Model = profiles::Entity::find_by_id(id)
+        .one(&state.db)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    if let Some(TypedHeader(if_match)) = if_match {
+        let etag = profile.etag().ok_or(StatusCode::INTERNAL_SERVER_ERROR)?;
+        if !if_match.precondition_passes(&etag) {
+            return Err(StatusCode::PRECONDITION_FAILED);
+        }
+    }
+
+    // perform updates inside of transaction
+    let txn = state
+        .db
+        .begin()
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let (profile, changed_postcodes) = apply_update(profile, input, &state, &txn).await?;
+
+    txn.commit()
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    state.postcodes_changed(changed_postcodes);
+
+    let etag = profile.etag().ok_or(StatusCode::INTERNAL_SERVER_ERROR)?;
+    let query_result: QueryResult = profile.into();
+    let body =
+        serde_json::to_string(&query_result).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok((TypedHeader(etag), body).into_response())
+}
@@ -0,0 +1,215 @@
+use axum::{extract::State, http::StatusCode, Json};
+use sea_orm::{ColumnTrait, EntityTrait, QueryFilter, QuerySelect, TransactionTrait};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+use crate::database::profiles;
+use crate::utils::validation::{FieldError, Validate};
+
+use super::app_state::AppState;
+use super::patch_craftsmen::{apply_update, QueryResult, ReqBody, Updated};
+
+const MAX_BATCH_SIZE: usize = 10_000;
+
+/// number of items applied per transaction
+const CHUNK_SIZE: usize = 100;
+
+#[derive(Deserialize)]
+pub struct BatchItem {
+    id: i32,
+    #[serde(flatten)]
+    update: ReqBody,
+}
+
+#[derive(Serialize)]
+pub struct ItemError {
+    index: usize,
+    id: i32,
+    errors: Vec<FieldError>,
+}
+
+#[derive(Serialize)]
+pub struct ItemResult {
+    id: i32,
+    status: u16,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    updated: Option<Updated>,
+}
+
+#[derive(Serialize)]
+pub struct Response {
+    results: Vec<ItemResult>,
+}
+
+fn internal_error<E>(_: E) -> (StatusCode, String) {
+    (StatusCode::INTERNAL_SERVER_ERROR, String::new())
+}
+
+fn id_error(message: &'static str) -> Vec<FieldError> {
+    vec![FieldError {
+        field: "id",
+        message,
+    }]
+}
+
+fn validate_item(
+    item: &BatchItem,
+    seen: &mut HashSet<i32>,
+    state: &AppState,
+) -> Option<Vec<FieldError>> {
+    if !seen.insert(item.id) {
+        return Some(id_error("duplicate id"));
+    }
+
+    let ReqBody {
+        max_driving_distance,
+        profile_picture_score,
+        profile_description_score,
+    } = &item.update;
+
+    if max_driving_distance.is_none()
+        && profile_picture_score.is_none()
+        && profile_description_score.is_none()
+    {
+        // not about a single field, so reported for the item as a whole
+        return Some(vec![FieldError {
+            field: "",
+            message: "at least one value has to be updated",
+        }]);
+    }
+
+    item.update
+        .validate(state)
+        .err()
+        .map(|errors| errors.errors)
+}
+
+/// Updates many profiles at once. The whole batch is validated up front and rejected with
+/// per-item errors if anything is off, otherwise every item gets its own result.
+pub async fn handler(
+    State(state): State<AppState>,
+    Json(items): Json<Vec<BatchItem>>,
+) -> Result<String, (StatusCode, String)> {
+    if items.is_empty() {
+        return Err((StatusCode::BAD_REQUEST, String::new()));
+    }
+
+    if items.len() > MAX_BATCH_SIZE {
+        return Err((StatusCode::PAYLOAD_TOO_LARGE, String::new()));
+    }
+
+    let mut seen = HashSet::new();
+    let mut errors: Vec<ItemError> = items
+        .iter()
+        .enumerate()
+        .filter_map(|(index, item)| {
+            validate_item(item, &mut seen, &state).map(|errors| ItemError {
+                index,
+                id: item.id,
+                errors,
+            })
+        })
+        .collect();
+
+    let existing: HashSet<i32> = profiles::Entity::find()
+        .select_only()
+        .column(profiles::Column::Id)
+        .filter(profiles::Column::Id.is_in(seen))
+        .into_tuple()
+        .all(&state.db)
+        .await
+        .map_err(internal_error)?
+        .into_iter()
+        .collect();
+
+    errors.extend(
+        items
+            .iter()
+            .enumerate()
+            .filter(|(_, item)| !existing.contains(&item.id))
+            .map(|(index, item)| ItemError {
+                index,
+                id: item.id,
+                errors: id_error("profile not found"),
+            }),
+    );
+
+    if !errors.is_empty() {
+        errors.sort_by_key(|error| error.index);
+        let body = serde_json::to_string(&errors).map_err(internal_error)?;
+        return Err((StatusCode::UNPROCESSABLE_ENTITY, body));
+    }
+
+    let mut results = Vec::with_capacity(items.len());
+    let mut items = items.into_iter().peekable();
+
+    while items.peek().is_some() {
+        let chunk: Vec<BatchItem> = items.by_ref().take(CHUNK_SIZE).collect();
+
+        let txn = state.db.begin().await.map_err(internal_error)?;
+
+        let mut chunk_profiles: HashMap<i32, profiles::Model> = profiles::Entity::find()
+            .filter(profiles::Column::Id.is_in(chunk.iter().map(|item| item.id)))
+            .all(&txn)
+            .await
+            .map_err(internal_error)?
+            .into_iter()
+            .map(|profile| (profile.id, profile))
+            .collect();
+
+        let mut chunk_results = Vec::with_capacity(chunk.len());
+        let mut changed_postcodes = Vec::new();
+
+        for BatchItem { id, update } in chunk {
+            let Some(profile) = chunk_profiles.remove(&id) else {
+                // deleted since validation
+                chunk_results.push((id, Err(StatusCode::NOT_FOUND)));
+                continue;
+            };
+
+            // savepoint per item, so a failing item doesn't take the rest of the chunk with it
+            let item_txn = txn.begin().await.map_err(internal_error)?;
+
+            match apply_update(profile, update, &state, &item_txn).await {
+                Ok((profile, postcodes)) => {
+                    item_txn.commit().await.map_err(internal_error)?;
+                    changed_postcodes.extend(postcodes);
+                    chunk_results.push((id, Ok(profile)));
+                }
+                Err(status) => {
+                    item_txn.rollback().await.map_err(internal_error)?;
+                    chunk_results.push((id, Err(status)));
+                }
+            }
+        }
+
+        let committed = txn.commit().await.is_ok();
+
+        if committed {
+            state.postcodes_changed(changed_postcodes);
+        }
+
+        results.extend(chunk_results.into_iter().map(|(id, result)| match result {
+            Ok(profile) if committed => {
+                let QueryResult { updated, .. } = profile.into();
+                ItemResult {
+                    id,
+                    status: StatusCode::OK.as_u16(),
+                    updated: Some(updated),
+                }
+            }
+            Ok(_) => ItemResult {
+                id,
+                status: StatusCode::INTERNAL_SERVER_ERROR.as_u16(),
+                updated: None,
+            },
+            Err(status) => ItemResult {
+                id,
+                status: status.as_u16(),
+                updated: None,
+            },
+        }));
+    }
+
+    serde_json::to_string(&Response { results }).map_err(internal_error)
+}
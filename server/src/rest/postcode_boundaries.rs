@@ -0,0 +1,152 @@
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+};
+use sea_orm::{ColumnTrait, EntityTrait, QueryFilter, TransactionTrait};
+use sea_query::Expr;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+use crate::{
+    database::postcode,
+    utils::geometry::Geometry,
+    utils::postcode_utils::{load_postcodes, CoverageMethod, PostcodeId, DEFAULT_COUNTRY},
+    utils::validation::{Validate, ValidationErrors, Validator},
+};
+
+use super::app_state::AppState;
+use super::extract::ValidJson;
+use super::patch_craftsmen::materialize_postcodes;
+
+#[derive(Deserialize)]
+pub struct ReqQuery {
+    /// recompute the ranks of the postcodes right away, which only makes a difference if
+    /// coverage is decided by boundaries and is done by default then
+    rebuild: Option<bool>,
+}
+
+/// GeoJSON FeatureCollection, e.g. converted from a shapefile
+#[derive(Deserialize)]
+pub struct FeatureCollection {
+    features: Vec<Feature>,
+}
+
+#[derive(Deserialize)]
+pub struct Feature {
+    properties: BoundaryProperties,
+    /// `null` removes the boundary of the postcode
+    geometry: Option<Geometry>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BoundaryProperties {
+    /// ISO 3166-1 alpha-2 code, Germany if missing
+    country_code: Option<String>,
+    postcode: String,
+}
+
+impl Feature {
+    fn postcode_id(&self) -> PostcodeId {
+        let country_code = self
+            .properties
+            .country_code
+            .as_deref()
+            .unwrap_or(DEFAULT_COUNTRY);
+
+        PostcodeId::new(country_code, &self.properties.postcode)
+    }
+}
+
+fn boundaries(features: &[Feature], _: &AppState) -> Result<(), &'static str> {
+    features
+        .iter()
+        .filter_map(|feature| feature.geometry.as_ref())
+        .try_for_each(Geometry::check)
+}
+
+impl Validate for FeatureCollection {
+    fn validate(&self, state: &AppState) -> Result<(), ValidationErrors> {
+        Validator::new(state)
+            .field("features", self.features.as_slice(), &[boundaries])
+            .finish()
+    }
+}
+
+#[derive(Serialize)]
+pub struct Response {
+    updated: usize,
+    /// postcodes of features that aren't in the `postcode` table, they are skipped
+    unknown: Vec<PostcodeId>,
+    rebuilt: usize,
+}
+
+/// Stores the boundaries of the postcodes in the collection. Postcodes that aren't part of it
+/// keep theirs.
+pub async fn import(
+    Query(ReqQuery { rebuild }): Query<ReqQuery>,
+    State(state): State<AppState>,
+    ValidJson(FeatureCollection { features }): ValidJson<FeatureCollection>,
+) -> Result<String, StatusCode> {
+    let txn = state
+        .db
+        .begin()
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let mut updated = HashSet::new();
+    let mut unknown = Vec::new();
+
+    for feature in features {
+        let id = feature.postcode_id();
+        let boundary = feature
+            .geometry
+            .map(serde_json::to_value)
+            .transpose()
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+        let result = postcode::Entity::update_many()
+            .col_expr(postcode::Column::Boundary, Expr::value(boundary))
+            .filter(postcode::Column::CountryCode.eq(&id.country_code))
+            .filter(postcode::Column::Postcode.eq(&id.postcode))
+            .exec(&txn)
+            .await
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+        if result.rows_affected == 0 {
+            unknown.push(id);
+        } else {
+            updated.insert(id);
+        }
+    }
+
+    let postcodes = load_postcodes(&txn)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let rebuild = rebuild.unwrap_or(state.distances.coverage == CoverageMethod::Boundary);
+    let changed_postcodes = if rebuild {
+        let changed: Vec<_> = postcodes
+            .iter()
+            .filter(|postcode| updated.contains(postcode.id()))
+            .collect();
+        materialize_postcodes(&changed, &state.distances, &txn).await?
+    } else {
+        Vec::new()
+    };
+
+    state
+        .commit_postcodes(postcodes, txn)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let response = Response {
+        updated: updated.len(),
+        unknown,
+        rebuilt: changed_postcodes.len(),
+    };
+
+    state.postcodes_changed(changed_postcodes);
+
+    serde_json::to_string(&response).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)
+}
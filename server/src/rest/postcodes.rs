@@ -0,0 +1,92 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+};
+use sea_orm::PaginatorTrait;
+use serde::{Deserialize, Serialize};
+
+use crate::utils::postcode_utils::{PostcodeId, PostcodeInfo, DEFAULT_COUNTRY};
+
+use super::app_state::AppState;
+use super::get_craftsmen::ranked_profiles;
+
+const LIMIT: usize = 20;
+
+#[derive(Deserialize)]
+pub struct ReqQuery {
+    #[serde(default)]
+    prefix: String,
+    /// ISO 3166-1 alpha-2 code, Germany if missing
+    country: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct Response {
+    postcodes: Vec<PostcodeInfo>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PostcodeDetails {
+    #[serde(flatten)]
+    info: PostcodeInfo,
+    extension_group: String,
+    /// km granted on top of every driving radius
+    extension_offset_km: f64,
+    /// craftsmen that show up in unfiltered searches for the postcode
+    craftsmen: u64,
+}
+
+/// Postcodes starting with the prefix, for autocompletion. Served from the postcodes in
+/// memory, so it doesn't hit the database.
+pub async fn list(
+    Query(ReqQuery { prefix, country }): Query<ReqQuery>,
+    State(state): State<AppState>,
+) -> Result<String, StatusCode> {
+    let PostcodeId {
+        country_code,
+        postcode: prefix,
+    } = PostcodeId::new(country.as_deref().unwrap_or(DEFAULT_COUNTRY), &prefix);
+
+    // already ordered by country and code
+    let postcodes = state
+        .postcodes()
+        .iter()
+        .filter(|postcode| {
+            let id = postcode.id();
+            id.country_code == country_code && id.postcode.starts_with(&prefix)
+        })
+        .take(LIMIT)
+        .map(|postcode| postcode.info())
+        .collect();
+
+    serde_json::to_string(&Response { postcodes }).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)
+}
+
+pub async fn get(
+    Path(code): Path<String>,
+    Query(ReqQuery { country, .. }): Query<ReqQuery>,
+    State(state): State<AppState>,
+) -> Result<String, StatusCode> {
+    let id = PostcodeId::new(country.as_deref().unwrap_or(DEFAULT_COUNTRY), &code);
+
+    let postcodes = state.postcodes();
+    let postcode = postcodes
+        .iter()
+        .find(|postcode| postcode.id() == &id)
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    let craftsmen = ranked_profiles(&id, None)
+        .count(&state.db)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let details = PostcodeDetails {
+        info: postcode.info(),
+        extension_group: postcode.extension_group().to_owned(),
+        extension_offset_km: postcode.offset(),
+        craftsmen,
+    };
+
+    serde_json::to_string(&details).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)
+}
@@ -0,0 +1,65 @@
+use axum::{
+    extract::{Path, State},
+    headers::{authorization::Bearer, Authorization},
+    http::StatusCode,
+    TypedHeader,
+};
+use sea_orm::{sea_query::OnConflict, ActiveValue, EntityTrait};
+use serde::Serialize;
+
+use crate::{
+    database::{profile_logins, profiles},
+    utils::auth::{authorize, hash_token, new_token},
+};
+
+use super::app_state::AppState;
+
+#[derive(Serialize)]
+pub struct Response {
+    /// only shown once, it can't be recovered from what is stored
+    token: String,
+}
+
+/// Issues a new login code for the craftsman, replacing the previous one.
+pub async fn issue(
+    Path(id): Path<i32>,
+    State(AppState { db, .. }): State<AppState>,
+) -> Result<(StatusCode, String), StatusCode> {
+    profiles::Entity::find_by_id(id)
+        .one(&db)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    let token = new_token();
+
+    profile_logins::Entity::insert(profile_logins::ActiveModel {
+        profile_id: ActiveValue::Set(id),
+        token_hash: ActiveValue::Set(hash_token(&token)),
+        ..Default::default()
+    })
+    .on_conflict(
+        OnConflict::column(profile_logins::Column::ProfileId)
+            .update_column(profile_logins::Column::TokenHash)
+            .to_owned(),
+    )
+    .exec(&db)
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let body = serde_json::to_string(&Response { token })
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok((StatusCode::CREATED, body))
+}
+
+/// 204 if the bearer token is the login code of the craftsman, so the frontend can check a
+/// code before it keeps it.
+pub async fn check(
+    Path(id): Path<i32>,
+    State(AppState { db, .. }): State<AppState>,
+    auth: Option<TypedHeader<Authorization<Bearer>>>,
+) -> Result<StatusCode, StatusCode> {
+    authorize(id, auth, &db).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
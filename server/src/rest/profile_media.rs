@@ -0,0 +1,148 @@
+use axum::{
+    body::Bytes,
+    extract::{Path, State},
+    headers::{authorization::Bearer, Authorization, ContentType},
+    http::{header, StatusCode},
+    response::IntoResponse,
+    TypedHeader,
+};
+use sea_orm::{sea_query::OnConflict, ActiveValue, EntityTrait};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    database::profile_media,
+    utils::auth::authorize,
+    utils::validation::{self, Validate, ValidationErrors, Validator},
+};
+
+use super::app_state::AppState;
+use super::extract::ValidJson;
+
+/// formats browsers can show, larger bodies are already rejected by axum's 2 MB limit
+const PICTURE_TYPES: [&str; 3] = ["image/jpeg", "image/png", "image/webp"];
+
+#[derive(Serialize, Deserialize)]
+pub struct Description {
+    description: String,
+}
+
+impl Validate for Description {
+    fn validate(&self, state: &AppState) -> Result<(), ValidationErrors> {
+        Validator::new(state)
+            .field(
+                "description",
+                self.description.as_str(),
+                &[validation::not_blank, validation::description_length],
+            )
+            .finish()
+    }
+}
+
+async fn find_media(state: &AppState, id: i32) -> Result<Option<profile_media::Model>, StatusCode> {
+    profile_media::Entity::find_by_id(id)
+        .one(&state.db)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)
+}
+
+/// Stores the given columns of the media of the profile, the others are kept.
+async fn store(
+    state: &AppState,
+    media: profile_media::ActiveModel,
+    columns: &[profile_media::Column],
+) -> Result<(), StatusCode> {
+    profile_media::Entity::insert(media)
+        .on_conflict(
+            OnConflict::column(profile_media::Column::ProfileId)
+                .update_columns(columns.iter().copied())
+                .to_owned(),
+        )
+        .exec(&state.db)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(())
+}
+
+/// the uploaded picture of the craftsman, 404 if there is none
+pub async fn get_picture(
+    Path(id): Path<i32>,
+    State(state): State<AppState>,
+) -> Result<impl IntoResponse, StatusCode> {
+    let media = find_media(&state, id).await?;
+    let (picture, picture_type) = media
+        .and_then(|media| media.picture.zip(media.picture_type))
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    Ok(([(header::CONTENT_TYPE, picture_type)], picture))
+}
+
+/// Replaces the picture of the craftsman, only they can upload it.
+pub async fn put_picture(
+    Path(id): Path<i32>,
+    State(state): State<AppState>,
+    auth: Option<TypedHeader<Authorization<Bearer>>>,
+    content_type: Option<TypedHeader<ContentType>>,
+    picture: Bytes,
+) -> Result<StatusCode, StatusCode> {
+    authorize(id, auth, &state.db).await?;
+
+    let picture_type = content_type
+        .map(|TypedHeader(content_type)| content_type.to_string())
+        .filter(|content_type| PICTURE_TYPES.contains(&content_type.as_str()))
+        .ok_or(StatusCode::UNSUPPORTED_MEDIA_TYPE)?;
+    if picture.is_empty() {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let media = profile_media::ActiveModel {
+        profile_id: ActiveValue::Set(id),
+        picture: ActiveValue::Set(Some(picture.to_vec())),
+        picture_type: ActiveValue::Set(Some(picture_type)),
+        ..Default::default()
+    };
+    store(
+        &state,
+        media,
+        &[
+            profile_media::Column::Picture,
+            profile_media::Column::PictureType,
+        ],
+    )
+    .await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// the description the craftsman wrote, 404 if there is none
+pub async fn get_description(
+    Path(id): Path<i32>,
+    State(state): State<AppState>,
+) -> Result<String, StatusCode> {
+    let description = find_media(&state, id)
+        .await?
+        .and_then(|media| media.description)
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    serde_json::to_string(&Description { description })
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)
+}
+
+/// Replaces the description of the craftsman, only they can write it.
+pub async fn put_description(
+    Path(id): Path<i32>,
+    State(state): State<AppState>,
+    auth: Option<TypedHeader<Authorization<Bearer>>>,
+    ValidJson(Description { description }): ValidJson<Description>,
+) -> Result<StatusCode, StatusCode> {
+    authorize(id, auth, &state.db).await?;
+
+    let media = profile_media::ActiveModel {
+        profile_id: ActiveValue::Set(id),
+        description: ActiveValue::Set(Some(description)),
+        ..Default::default()
+    };
+    store(&state, media, &[profile_media::Column::Description]).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
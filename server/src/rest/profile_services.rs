@@ -0,0 +1,186 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+};
+use sea_orm::{
+    ColumnTrait, EntityTrait, FromQueryResult, JoinType, QueryFilter, QueryOrder, QuerySelect,
+    RelationTrait, TransactionTrait,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    database::{profile_services, profiles, services},
+    utils::validation::{self, Validate, ValidationErrors, Validator},
+};
+
+use super::app_state::AppState;
+use super::extract::ValidJson;
+use super::patch_craftsmen::materialize_ranks;
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReqBody {
+    /// radius for this service in meters, the one of the profile is used if missing
+    max_driving_distance: Option<f64>,
+}
+
+impl Validate for ReqBody {
+    fn validate(&self, state: &AppState) -> Result<(), ValidationErrors> {
+        Validator::new(state)
+            .optional(
+                "maxDrivingDistance",
+                &self.max_driving_distance,
+                &[
+                    validation::finite,
+                    validation::positive,
+                    validation::driving_distance,
+                ],
+            )
+            .finish()
+    }
+}
+
+#[derive(Serialize, FromQueryResult)]
+#[serde(rename_all = "camelCase")]
+pub struct ProfileService {
+    id: i32,
+    name: String,
+    max_driving_distance: Option<f64>,
+}
+
+#[derive(Serialize)]
+pub struct Response {
+    services: Vec<ProfileService>,
+}
+
+pub async fn list(
+    Path(id): Path<i32>,
+    State(AppState { db, .. }): State<AppState>,
+) -> Result<String, StatusCode> {
+    profiles::Entity::find_by_id(id)
+        .one(&db)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    let services: Vec<ProfileService> = services::Entity::find()
+        .column_as(
+            profile_services::Column::MaxDrivingDistance,
+            "max_driving_distance",
+        )
+        .join(
+            JoinType::InnerJoin,
+            services::Relation::ProfileServices.def(),
+        )
+        .filter(profile_services::Column::ProfileId.eq(id))
+        .order_by_asc(services::Column::Name)
+        .into_model()
+        .all(&db)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    serde_json::to_string(&Response { services }).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)
+}
+
+/// Adds the service to the profile or changes its radius override.
+pub async fn put(
+    Path((id, service_id)): Path<(i32, i32)>,
+    State(state): State<AppState>,
+    ValidJson(ReqBody {
+        max_driving_distance,
+    }): ValidJson<ReqBody>,
+) -> Result<String, StatusCode> {
+    let txn = state
+        .db
+        .begin()
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    // locked, so concurrent updates of the profile don't materialize stale radii
+    let profile = profiles::Entity::find_by_id(id)
+        .lock_exclusive()
+        .one(&txn)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    let service = services::Entity::find_by_id(service_id)
+        .one(&txn)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    profile_services::Entity::insert(profile_services::ActiveModel::from(
+        profile_services::Model {
+            profile_id: id,
+            service_id,
+            max_driving_distance,
+        },
+    ))
+    .on_conflict(
+        sea_query::OnConflict::columns([
+            profile_services::Column::ProfileId,
+            profile_services::Column::ServiceId,
+        ])
+        .update_columns([profile_services::Column::MaxDrivingDistance])
+        .to_owned(),
+    )
+    .exec(&txn)
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let changed_postcodes =
+        materialize_ranks(&profile, &state.postcodes(), &state.distances, &txn).await?;
+
+    txn.commit()
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    state.postcodes_changed(changed_postcodes);
+
+    let profile_service = ProfileService {
+        id: service.id,
+        name: service.name,
+        max_driving_distance,
+    };
+
+    serde_json::to_string(&profile_service).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)
+}
+
+pub async fn delete(
+    Path((id, service_id)): Path<(i32, i32)>,
+    State(state): State<AppState>,
+) -> Result<StatusCode, StatusCode> {
+    let txn = state
+        .db
+        .begin()
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let profile = profiles::Entity::find_by_id(id)
+        .lock_exclusive()
+        .one(&txn)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    let deleted = profile_services::Entity::delete_by_id((id, service_id))
+        .exec(&txn)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    if deleted.rows_affected == 0 {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    let changed_postcodes =
+        materialize_ranks(&profile, &state.postcodes(), &state.distances, &txn).await?;
+
+    txn.commit()
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    state.postcodes_changed(changed_postcodes);
+
+    Ok(StatusCode::NO_CONTENT)
+}
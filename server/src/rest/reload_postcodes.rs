@@ -0,0 +1,124 @@
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+};
+use sea_orm::{EntityTrait, QueryFilter, TransactionTrait};
+use sea_query::Expr;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+use crate::database::road_distances;
+use crate::utils::postcode_utils::{load_postcodes, PostcodeId};
+
+use super::app_state::AppState;
+use super::patch_craftsmen::{materialize_postcodes, CHUNK_SIZE};
+
+#[derive(Deserialize)]
+pub struct ReqQuery {
+    /// recompute the ranks of added and moved postcodes right away, instead of waiting for
+    /// the profiles to be updated
+    #[serde(default)]
+    rebuild: bool,
+}
+
+#[derive(Serialize)]
+pub struct Response {
+    postcodes: usize,
+    added: usize,
+    changed: usize,
+    removed: usize,
+    rebuilt: usize,
+}
+
+/// Reloads the postcodes from the database, e.g. after postcodes were added or corrected.
+pub async fn handler(
+    Query(ReqQuery { rebuild }): Query<ReqQuery>,
+    State(state): State<AppState>,
+) -> Result<String, StatusCode> {
+    let snapshot = state.postcodes();
+    let old_postcodes: HashMap<&PostcodeId, _> = snapshot
+        .iter()
+        .map(|postcode| (postcode.id(), postcode))
+        .collect();
+
+    let txn = state
+        .db
+        .begin()
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let postcodes = load_postcodes(&txn)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let (mut added, mut changed) = (Vec::new(), Vec::new());
+    for postcode in &postcodes {
+        match old_postcodes.get(postcode.id()) {
+            None => added.push(postcode),
+            Some(old) if !old.same_location(postcode) => changed.push(postcode),
+            Some(_) => {}
+        }
+    }
+
+    // driving distances to postcodes that moved have to be asked for again
+    let moved = changed
+        .iter()
+        .filter(|postcode| {
+            let old = old_postcodes[postcode.id()].location();
+            let new = postcode.location();
+            old.latitude() != new.latitude() || old.longitude() != new.longitude()
+        })
+        .map(|postcode| {
+            let id = postcode.id();
+            (id.country_code.clone(), id.postcode.clone())
+        })
+        .collect::<Vec<_>>();
+
+    for chunk in moved.chunks(CHUNK_SIZE) {
+        road_distances::Entity::delete_many()
+            .filter(
+                Expr::tuple([
+                    Expr::col(road_distances::Column::CountryCode).into(),
+                    Expr::col(road_distances::Column::Postcode).into(),
+                ])
+                .in_tuples(chunk.iter().cloned()),
+            )
+            .exec(&txn)
+            .await
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    }
+
+    // ranks of removed postcodes are gone with them, only cached results remain
+    let known: HashSet<&PostcodeId> = postcodes.iter().map(|postcode| postcode.id()).collect();
+    let mut changed_postcodes: Vec<PostcodeId> = old_postcodes
+        .into_keys()
+        .filter(|id| !known.contains(id))
+        .cloned()
+        .collect();
+
+    let response = Response {
+        postcodes: postcodes.len(),
+        added: added.len(),
+        changed: changed.len(),
+        removed: changed_postcodes.len(),
+        rebuilt: if rebuild {
+            added.len() + changed.len()
+        } else {
+            0
+        },
+    };
+
+    if rebuild {
+        let rebuilt: Vec<_> = added.into_iter().chain(changed).collect();
+        changed_postcodes.extend(materialize_postcodes(&rebuilt, &state.distances, &txn).await?);
+    }
+
+    state
+        .commit_postcodes(postcodes, txn)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    state.postcodes_changed(changed_postcodes);
+
+    serde_json::to_string(&response).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)
+}
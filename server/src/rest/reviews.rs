@@ -0,0 +1,225 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    Json,
+};
+use sea_orm::{
+    ActiveModelTrait, ActiveValue, ColumnTrait, ConnectionTrait, EntityTrait, QueryFilter,
+    QueryOrder, QuerySelect, TransactionTrait,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    database::{profiles, reviews, sea_orm_active_enums::ReviewStatus},
+    utils::scoring,
+    utils::validation::{self, Validate, ValidationErrors, Validator},
+};
+
+use super::app_state::AppState;
+use super::extract::ValidJson;
+use super::patch_craftsmen::update_review_score;
+
+const LIMIT: u64 = 20;
+
+#[derive(Deserialize)]
+pub struct NewReview {
+    rating: i16,
+    author: String,
+    #[serde(default)]
+    text: String,
+}
+
+impl Validate for NewReview {
+    fn validate(&self, state: &AppState) -> Result<(), ValidationErrors> {
+        Validator::new(state)
+            .field("rating", &self.rating, &[validation::rating])
+            .field("author", self.author.as_str(), &[validation::not_blank])
+            .finish()
+    }
+}
+
+#[derive(Deserialize)]
+pub struct Moderation {
+    status: ReviewStatus,
+}
+
+#[derive(Deserialize)]
+pub struct ReqQuery {
+    offset: Option<u64>,
+    status: Option<ReviewStatus>,
+}
+
+#[derive(Serialize)]
+pub struct Rating {
+    /// Bayesian average of the approved reviews in stars
+    stars: Option<f64>,
+    count: i64,
+}
+
+#[derive(Serialize)]
+pub struct Response {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    rating: Option<Rating>,
+    reviews: Vec<reviews::Model>,
+}
+
+/// number and sum of the approved ratings of a profile
+async fn aggregate<C: ConnectionTrait>(profile_id: i32, db: &C) -> Result<(i64, i64), StatusCode> {
+    let (count, sum): (i64, Option<i64>) = reviews::Entity::find()
+        .select_only()
+        .column_as(reviews::Column::Id.count(), "count")
+        .column_as(reviews::Column::Rating.sum(), "sum")
+        .filter(reviews::Column::ProfileId.eq(profile_id))
+        .filter(reviews::Column::Status.eq(ReviewStatus::Approved))
+        .into_tuple()
+        .one(db)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .unwrap_or_default();
+
+    Ok((count, sum.unwrap_or_default()))
+}
+
+pub async fn create(
+    Path(id): Path<i32>,
+    State(AppState { db, .. }): State<AppState>,
+    ValidJson(NewReview {
+        rating,
+        author,
+        text,
+    }): ValidJson<NewReview>,
+) -> Result<(StatusCode, String), StatusCode> {
+    profiles::Entity::find_by_id(id)
+        .one(&db)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    // reviews only count once they are approved by a moderator
+    let review = reviews::ActiveModel {
+        profile_id: ActiveValue::Set(id),
+        rating: ActiveValue::Set(rating),
+        author: ActiveValue::Set(author.trim().to_owned()),
+        text: ActiveValue::Set(text),
+        ..Default::default()
+    }
+    .insert(&db)
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let body = serde_json::to_string(&review).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok((StatusCode::CREATED, body))
+}
+
+/// approved reviews of a craftsman, newest first
+pub async fn list(
+    Path(id): Path<i32>,
+    Query(ReqQuery { offset, .. }): Query<ReqQuery>,
+    State(AppState { db, .. }): State<AppState>,
+) -> Result<String, StatusCode> {
+    profiles::Entity::find_by_id(id)
+        .one(&db)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    let reviews = reviews::Entity::find()
+        .filter(reviews::Column::ProfileId.eq(id))
+        .filter(reviews::Column::Status.eq(ReviewStatus::Approved))
+        .order_by_desc(reviews::Column::CreatedAt)
+        .offset(offset)
+        .limit(LIMIT)
+        .all(&db)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let (count, sum) = aggregate(id, &db).await?;
+    let rating = Rating {
+        stars: scoring::bayesian_rating(count, sum),
+        count,
+    };
+
+    serde_json::to_string(&Response {
+        rating: Some(rating),
+        reviews,
+    })
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)
+}
+
+/// moderation queue, pending reviews unless asked for another status
+pub async fn queue(
+    Query(ReqQuery { offset, status }): Query<ReqQuery>,
+    State(AppState { db, .. }): State<AppState>,
+) -> Result<String, StatusCode> {
+    let reviews = reviews::Entity::find()
+        .filter(reviews::Column::Status.eq(status.unwrap_or(ReviewStatus::Pending)))
+        .order_by_asc(reviews::Column::CreatedAt)
+        .offset(offset)
+        .limit(LIMIT)
+        .all(&db)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    serde_json::to_string(&Response {
+        rating: None,
+        reviews,
+    })
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)
+}
+
+/// Approves or rejects a review. The score and ranks of the craftsman follow right away
+/// whenever the set of approved reviews changes.
+pub async fn moderate(
+    Path(id): Path<i32>,
+    State(state): State<AppState>,
+    Json(Moderation { status }): Json<Moderation>,
+) -> Result<String, StatusCode> {
+    let txn = state
+        .db
+        .begin()
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let review = reviews::Entity::find_by_id(id)
+        .lock_exclusive()
+        .one(&txn)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    let was_approved = review.status == ReviewStatus::Approved;
+    let is_approved = status == ReviewStatus::Approved;
+
+    let mut review: reviews::ActiveModel = review.into();
+    review.status = ActiveValue::Set(status);
+    let review = review
+        .update(&txn)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let mut changed_postcodes = Vec::new();
+
+    if was_approved != is_approved {
+        // the lock keeps the version stable until the score is written
+        let profile = profiles::Entity::find_by_id(review.profile_id)
+            .lock_exclusive()
+            .one(&txn)
+            .await
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+            .ok_or(StatusCode::INTERNAL_SERVER_ERROR)?;
+
+        let (count, sum) = aggregate(profile.id, &txn).await?;
+        let review_score = scoring::bayesian_rating(count, sum).map(scoring::review_score);
+
+        (_, changed_postcodes) = update_review_score(profile, review_score, &txn).await?;
+    }
+
+    txn.commit()
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    state.postcodes_changed(changed_postcodes);
+
+    serde_json::to_string(&review).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)
+}
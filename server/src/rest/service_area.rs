@@ -0,0 +1,102 @@
+use axum::{
+    extract::{Path, State},
+    http::{header, StatusCode},
+    response::IntoResponse,
+};
+use sea_orm::{ActiveValue, EntityTrait, QuerySelect, TransactionTrait};
+
+use crate::{
+    database::profiles,
+    utils::geometry::{Geometry, GEOJSON},
+    utils::validation::{self, Validate, ValidationErrors, Validator},
+};
+
+use super::app_state::AppState;
+use super::extract::ValidJson;
+use super::patch_craftsmen::{materialize_ranks, update_profile};
+
+impl Validate for Geometry {
+    fn validate(&self, state: &AppState) -> Result<(), ValidationErrors> {
+        Validator::new(state)
+            .field("coordinates", self, &[validation::geometry])
+            .finish()
+    }
+}
+
+/// the service area of the craftsman as GeoJSON geometry, 404 if they only have a radius
+pub async fn get(
+    Path(id): Path<i32>,
+    State(AppState { db, .. }): State<AppState>,
+) -> Result<impl IntoResponse, StatusCode> {
+    let profile = profiles::Entity::find_by_id(id)
+        .one(&db)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    let area = profile.service_area.ok_or(StatusCode::NOT_FOUND)?;
+
+    let body = serde_json::to_string(&area).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(([(header::CONTENT_TYPE, GEOJSON)], body))
+}
+
+/// Stores the area, or removes it if `None`, and rematerializes the ranks of the profile.
+async fn replace(state: &AppState, id: i32, area: Option<&Geometry>) -> Result<(), StatusCode> {
+    let area = area
+        .map(serde_json::to_value)
+        .transpose()
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let txn = state
+        .db
+        .begin()
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    // locked, so the version read here is still the current one when updating
+    let profile = profiles::Entity::find_by_id(id)
+        .lock_exclusive()
+        .one(&txn)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    let version = profile.version;
+    let mut profile: profiles::ActiveModel = profile.into();
+    profile.service_area = ActiveValue::Set(area);
+
+    let profile = update_profile(profile, id, version, &txn).await?;
+    let changed_postcodes =
+        materialize_ranks(&profile, &state.postcodes(), &state.distances, &txn).await?;
+
+    txn.commit()
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    state.postcodes_changed(changed_postcodes);
+
+    Ok(())
+}
+
+/// Replaces the radius of the craftsman with a polygon or multipolygon. Radii of their
+/// services don't apply either while it is set.
+pub async fn put(
+    Path(id): Path<i32>,
+    State(state): State<AppState>,
+    ValidJson(area): ValidJson<Geometry>,
+) -> Result<String, StatusCode> {
+    replace(&state, id, Some(&area)).await?;
+
+    serde_json::to_string(&area).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)
+}
+
+/// goes back to the radius
+pub async fn delete(
+    Path(id): Path<i32>,
+    State(state): State<AppState>,
+) -> Result<StatusCode, StatusCode> {
+    replace(&state, id, None).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,158 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+};
+use sea_orm::{
+    ActiveModelTrait, ActiveValue, ColumnTrait, DbErr, EntityTrait, QueryFilter, QueryOrder,
+    QuerySelect, SqlErr, TransactionTrait,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    database::{profile_services, profiles, services},
+    utils::validation::{self, Validate, ValidationErrors, Validator},
+};
+
+use super::app_state::AppState;
+use super::extract::ValidJson;
+use super::patch_craftsmen::{materialize_ranks, ranked_postcodes};
+
+#[derive(Serialize, Deserialize)]
+pub struct ReqBody {
+    name: String,
+}
+
+impl Validate for ReqBody {
+    fn validate(&self, state: &AppState) -> Result<(), ValidationErrors> {
+        Validator::new(state)
+            .field("name", self.name.as_str(), &[validation::not_blank])
+            .finish()
+    }
+}
+
+#[derive(Serialize)]
+pub struct Response {
+    services: Vec<services::Model>,
+}
+
+fn map_write_err(err: DbErr) -> StatusCode {
+    match err.sql_err() {
+        // duplicate names, or deleting a service that job requests were made for
+        Some(SqlErr::UniqueConstraintViolation(_) | SqlErr::ForeignKeyConstraintViolation(_)) => {
+            StatusCode::CONFLICT
+        }
+        _ => StatusCode::INTERNAL_SERVER_ERROR,
+    }
+}
+
+pub async fn list(State(AppState { db, .. }): State<AppState>) -> Result<String, StatusCode> {
+    let services = services::Entity::find()
+        .order_by_asc(services::Column::Name)
+        .all(&db)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    serde_json::to_string(&Response { services }).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)
+}
+
+pub async fn get(
+    Path(id): Path<i32>,
+    State(AppState { db, .. }): State<AppState>,
+) -> Result<String, StatusCode> {
+    let service = services::Entity::find_by_id(id)
+        .one(&db)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    serde_json::to_string(&service).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)
+}
+
+pub async fn create(
+    State(AppState { db, .. }): State<AppState>,
+    ValidJson(ReqBody { name }): ValidJson<ReqBody>,
+) -> Result<(StatusCode, String), StatusCode> {
+    let service = services::ActiveModel {
+        name: ActiveValue::Set(name.trim().to_owned()),
+        ..Default::default()
+    }
+    .insert(&db)
+    .await
+    .map_err(map_write_err)?;
+
+    let body = serde_json::to_string(&service).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok((StatusCode::CREATED, body))
+}
+
+pub async fn update(
+    Path(id): Path<i32>,
+    State(AppState { db, .. }): State<AppState>,
+    ValidJson(ReqBody { name }): ValidJson<ReqBody>,
+) -> Result<String, StatusCode> {
+    let mut service: services::ActiveModel = services::Entity::find_by_id(id)
+        .one(&db)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::NOT_FOUND)?
+        .into();
+
+    service.name = ActiveValue::Set(name.trim().to_owned());
+    let service = service.update(&db).await.map_err(map_write_err)?;
+
+    serde_json::to_string(&service).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)
+}
+
+pub async fn delete(
+    Path(id): Path<i32>,
+    State(state): State<AppState>,
+) -> Result<StatusCode, StatusCode> {
+    let txn = state
+        .db
+        .begin()
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let offered_by: Vec<profile_services::Model> = profile_services::Entity::find()
+        .filter(profile_services::Column::ServiceId.eq(id))
+        .all(&txn)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let deleted = services::Entity::delete_by_id(id)
+        .exec(&txn)
+        .await
+        .map_err(map_write_err)?;
+
+    if deleted.rows_affected == 0 {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    // searches for this service change for every postcode its craftsmen serve, and craftsmen
+    // that travelled further for it shrink back to their regular radius
+    let mut changed_postcodes = Vec::new();
+    for profile_service in offered_by {
+        if profile_service.max_driving_distance.is_none() {
+            changed_postcodes.extend(ranked_postcodes(profile_service.profile_id, &txn).await?);
+            continue;
+        }
+
+        let profile = profiles::Entity::find_by_id(profile_service.profile_id)
+            .lock_exclusive()
+            .one(&txn)
+            .await
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+            .ok_or(StatusCode::INTERNAL_SERVER_ERROR)?;
+
+        changed_postcodes
+            .extend(materialize_ranks(&profile, &state.postcodes(), &state.distances, &txn).await?);
+    }
+
+    txn.commit()
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    state.postcodes_changed(changed_postcodes);
+
+    Ok(StatusCode::NO_CONTENT)
+}
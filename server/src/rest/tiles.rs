@@ -0,0 +1,27 @@
+use axum::{
+    extract::Path,
+    headers::CacheControl,
+    http::header,
+    response::{IntoResponse, Response},
+    TypedHeader,
+};
+use std::time::Duration;
+
+/// Stand-in for a tile server, so the map in the frontend works without external requests.
+/// Each tile is blank apart from its coordinates.
+pub async fn handler(Path((z, x, y)): Path<(u32, u32, u32)>) -> Response {
+    let tile = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"256\" height=\"256\">\
+            <rect width=\"256\" height=\"256\" fill=\"#f2efe9\" stroke=\"#d6d2c8\"/>\
+            <text x=\"128\" y=\"132\" text-anchor=\"middle\" font-family=\"sans-serif\" \
+                font-size=\"14\" fill=\"#a39e93\">{z}/{x}/{y}</text>\
+        </svg>"
+    );
+
+    (
+        TypedHeader(CacheControl::new().with_max_age(Duration::from_secs(24 * 60 * 60))),
+        [(header::CONTENT_TYPE, "image/svg+xml")],
+        tile,
+    )
+        .into_response()
+}
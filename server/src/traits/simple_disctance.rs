@@ -1,9 +1,66 @@
 use geoutils::Location;
+use std::fmt;
+use std::str::FromStr;
 
 const R: f64 = 6371.0;
 
+// WGS84 ellipsoid, in meters
+const WGS84_A: f64 = 6_378_137.0;
+const WGS84_F: f64 = 1.0 / 298.257_223_563;
+const WGS84_B: f64 = WGS84_A * (1.0 - WGS84_F);
+
+/// Vincenty doesn't converge for nearly antipodal points, haversine is used for those
+const VINCENTY_MAX_ITERATIONS: usize = 200;
+
+/// How distances between craftsmen and postcodes are calculated. Stored ranks are rebuilt on
+/// startup when it changes.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DistanceMetric {
+    /// spherical law of cosines, imprecise for short distances
+    Cosines,
+    #[default]
+    Haversine,
+    /// on the WGS84 ellipsoid, accurate to millimeters
+    Vincenty,
+}
+
+impl FromStr for DistanceMetric {
+    type Err = ();
+
+    fn from_str(metric: &str) -> Result<Self, Self::Err> {
+        match metric.to_lowercase().as_str() {
+            "cosines" => Ok(DistanceMetric::Cosines),
+            "haversine" => Ok(DistanceMetric::Haversine),
+            "vincenty" => Ok(DistanceMetric::Vincenty),
+            _ => Err(()),
+        }
+    }
+}
+
+impl fmt::Display for DistanceMetric {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            DistanceMetric::Cosines => "cosines",
+            DistanceMetric::Haversine => "haversine",
+            DistanceMetric::Vincenty => "vincenty",
+        })
+    }
+}
+
 pub trait SimpleDistance {
     fn calculate_simple_distance_km(&self, to: &Self) -> f64;
+
+    fn calculate_haversine_distance_km(&self, to: &Self) -> f64;
+
+    fn calculate_vincenty_distance_km(&self, to: &Self) -> f64;
+
+    fn calculate_distance_km(&self, to: &Self, metric: DistanceMetric) -> f64 {
+        match metric {
+            DistanceMetric::Cosines => self.calculate_simple_distance_km(to),
+            DistanceMetric::Haversine => self.calculate_haversine_distance_km(to),
+            DistanceMetric::Vincenty => self.calculate_vincenty_distance_km(to),
+        }
+    }
 }
 
 // extend Location type so more accurate distance calculation can be easily hot-swapped in,
@@ -13,6 +70,173 @@ impl SimpleDistance for Location {
         let (lat1, lon1) = (self.latitude().to_radians(), self.longitude().to_radians());
         let (lat2, lon2) = (to.latitude().to_radians(), to.longitude().to_radians());
 
-        (lat1.sin() * lat2.sin() + lat1.cos() * lat2.cos() * (lon1 - lon2).cos()).acos() * R
+        // rounding can push the cosine of (nearly) identical points above 1, which acos turns
+        // into NaN
+        let cos = lat1.sin() * lat2.sin() + lat1.cos() * lat2.cos() * (lon1 - lon2).cos();
+
+        cos.clamp(-1.0, 1.0).acos() * R
+    }
+
+    fn calculate_haversine_distance_km(&self, to: &Self) -> f64 {
+        let (lat1, lon1) = (self.latitude().to_radians(), self.longitude().to_radians());
+        let (lat2, lon2) = (to.latitude().to_radians(), to.longitude().to_radians());
+
+        let a = ((lat2 - lat1) / 2.0).sin().powi(2)
+            + lat1.cos() * lat2.cos() * ((lon2 - lon1) / 2.0).sin().powi(2);
+
+        2.0 * R * a.sqrt().min(1.0).asin()
+    }
+
+    fn calculate_vincenty_distance_km(&self, to: &Self) -> f64 {
+        vincenty_distance_m(self, to)
+            .map(|distance| distance / 1000.0)
+            .unwrap_or_else(|| self.calculate_haversine_distance_km(to))
+    }
+}
+
+/// inverse formula of Vincenty, `None` if it doesn't converge
+fn vincenty_distance_m(from: &Location, to: &Location) -> Option<f64> {
+    let l = (to.longitude() - from.longitude()).to_radians();
+    let u1 = ((1.0 - WGS84_F) * from.latitude().to_radians().tan()).atan();
+    let u2 = ((1.0 - WGS84_F) * to.latitude().to_radians().tan()).atan();
+    let (sin_u1, cos_u1) = u1.sin_cos();
+    let (sin_u2, cos_u2) = u2.sin_cos();
+
+    let mut lambda = l;
+    for _ in 0..VINCENTY_MAX_ITERATIONS {
+        let (sin_lambda, cos_lambda) = lambda.sin_cos();
+        let sin_sigma = ((cos_u2 * sin_lambda).powi(2)
+            + (cos_u1 * sin_u2 - sin_u1 * cos_u2 * cos_lambda).powi(2))
+        .sqrt();
+
+        if sin_sigma == 0.0 {
+            // same point
+            return Some(0.0);
+        }
+
+        let cos_sigma = sin_u1 * sin_u2 + cos_u1 * cos_u2 * cos_lambda;
+        let sigma = sin_sigma.atan2(cos_sigma);
+        let sin_alpha = cos_u1 * cos_u2 * sin_lambda / sin_sigma;
+        let cos2_alpha = 1.0 - sin_alpha.powi(2);
+
+        // both points on the equator
+        let cos_2sigma_m = if cos2_alpha == 0.0 {
+            0.0
+        } else {
+            cos_sigma - 2.0 * sin_u1 * sin_u2 / cos2_alpha
+        };
+
+        let c = WGS84_F / 16.0 * cos2_alpha * (4.0 + WGS84_F * (4.0 - 3.0 * cos2_alpha));
+        let previous = lambda;
+        lambda = l
+            + (1.0 - c)
+                * WGS84_F
+                * sin_alpha
+                * (sigma
+                    + c * sin_sigma
+                        * (cos_2sigma_m + c * cos_sigma * (-1.0 + 2.0 * cos_2sigma_m.powi(2))));
+
+        if (lambda - previous).abs() < 1e-12 {
+            let u2 = cos2_alpha * (WGS84_A.powi(2) - WGS84_B.powi(2)) / WGS84_B.powi(2);
+            let a = 1.0 + u2 / 16384.0 * (4096.0 + u2 * (-768.0 + u2 * (320.0 - 175.0 * u2)));
+            let b = u2 / 1024.0 * (256.0 + u2 * (-128.0 + u2 * (74.0 - 47.0 * u2)));
+            let delta_sigma = b
+                * sin_sigma
+                * (cos_2sigma_m
+                    + b / 4.0
+                        * (cos_sigma * (-1.0 + 2.0 * cos_2sigma_m.powi(2))
+                            - b / 6.0
+                                * cos_2sigma_m
+                                * (-3.0 + 4.0 * sin_sigma.powi(2))
+                                * (-3.0 + 4.0 * cos_2sigma_m.powi(2))));
+
+            return Some(WGS84_B * a * (sigma - delta_sigma));
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const METRICS: [DistanceMetric; 3] = [
+        DistanceMetric::Cosines,
+        DistanceMetric::Haversine,
+        DistanceMetric::Vincenty,
+    ];
+
+    /// the sphere is off from the ellipsoid by at most about 0.5 %
+    const SPHERE_TOLERANCE: f64 = 0.006;
+
+    /// points where Vincenty doesn't converge
+    const NEARLY_ANTIPODAL: [(f64, f64); 2] = [(0.0, 0.0), (0.5, 179.7)];
+
+    fn random_locations(seed: u64, count: usize) -> Vec<(Location, Location)> {
+        let mut rng = fastrand::Rng::with_seed(seed);
+        let mut location = || Location::new(rng.f64() * 180.0 - 90.0, rng.f64() * 360.0 - 180.0);
+        (0..count).map(|_| (location(), location())).collect()
+    }
+
+    #[test]
+    fn identical_points_are_zero_apart() {
+        for (from, _) in random_locations(1, 200) {
+            for metric in METRICS {
+                // the law of cosines loses about 0.1 m to rounding there
+                let distance = from.calculate_distance_km(&from, metric);
+                assert!(distance.abs() < 1e-3, "{metric:?}: {distance}");
+            }
+        }
+    }
+
+    #[test]
+    fn antipodal_points_are_finite() {
+        for (from, _) in random_locations(2, 200) {
+            let to = Location::new(-from.latitude(), from.longitude() - 180.0);
+            for metric in METRICS {
+                let distance = from.calculate_distance_km(&to, metric);
+                assert!(distance.is_finite(), "{metric:?}: {distance}");
+                assert!(
+                    (distance - std::f64::consts::PI * R).abs() < 0.01 * R,
+                    "{metric:?}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn metrics_are_symmetric() {
+        for (from, to) in random_locations(3, 500) {
+            for metric in METRICS {
+                let there = from.calculate_distance_km(&to, metric);
+                let back = to.calculate_distance_km(&from, metric);
+                assert!((there - back).abs() < 1e-6, "{metric:?}: {there} != {back}");
+            }
+        }
+    }
+
+    #[test]
+    fn haversine_is_close_to_vincenty() {
+        for (from, to) in random_locations(4, 1000) {
+            let haversine = from.calculate_haversine_distance_km(&to);
+            let vincenty = from.calculate_vincenty_distance_km(&to);
+            assert!(
+                (haversine - vincenty).abs() <= SPHERE_TOLERANCE * vincenty + 1e-6,
+                "{haversine} vs {vincenty}"
+            );
+        }
+    }
+
+    #[test]
+    fn vincenty_falls_back_to_haversine() {
+        let [(lat1, lon1), (lat2, lon2)] = NEARLY_ANTIPODAL;
+        let (from, to) = (Location::new(lat1, lon1), Location::new(lat2, lon2));
+
+        assert_eq!(vincenty_distance_m(&from, &to), None);
+        assert_eq!(
+            from.calculate_vincenty_distance_km(&to),
+            from.calculate_haversine_distance_km(&to)
+        );
     }
 }
@@ -0,0 +1,60 @@
+use axum::{
+    headers::{authorization::Bearer, Authorization},
+    http::StatusCode,
+    TypedHeader,
+};
+use sea_orm::{ConnectionTrait, EntityTrait};
+use sha2::{Digest, Sha256};
+
+use crate::database::profile_logins;
+
+/// New login code for a craftsman, 32 random bytes hex encoded.
+pub fn new_token() -> String {
+    hex::encode(rand::random::<[u8; 32]>())
+}
+
+/// the codes are random, so a fast hash is enough to not keep them in the clear
+pub fn hash_token(token: &str) -> String {
+    hex::encode(Sha256::digest(token.as_bytes()))
+}
+
+/// Lets the request through if it carries the login code of the profile as bearer token.
+/// 401 without a code, 403 if it isn't the one of this profile.
+pub async fn authorize<C: ConnectionTrait>(
+    profile_id: i32,
+    auth: Option<TypedHeader<Authorization<Bearer>>>,
+    db: &C,
+) -> Result<(), StatusCode> {
+    let TypedHeader(Authorization(bearer)) = auth.ok_or(StatusCode::UNAUTHORIZED)?;
+
+    let login = profile_logins::Entity::find_by_id(profile_id)
+        .one(db)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    match login {
+        Some(login) if login.token_hash == hash_token(bearer.token()) => Ok(()),
+        _ => Err(StatusCode::FORBIDDEN),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tokens_are_random_hex() {
+        let (first, second) = (new_token(), new_token());
+        assert_eq!(first.len(), 64);
+        assert!(first.chars().all(|c| c.is_ascii_hexdigit()));
+        assert_ne!(first, second);
+    }
+
+    #[test]
+    fn hashes_only_match_the_same_token() {
+        let token = new_token();
+        assert_eq!(hash_token(&token), hash_token(&token));
+        assert_ne!(hash_token(&token), hash_token(&new_token()));
+        assert_ne!(hash_token(&token), token);
+    }
+}
@@ -0,0 +1,59 @@
+use chrono::NaiveDateTime;
+use sea_query::{Expr, SimpleExpr};
+
+use crate::database::filtered_ranks;
+
+/// Lowers the rank of craftsmen without availability in the next days. A factor of 1 leaves
+/// the ranking as it is.
+#[derive(Clone, Copy, Debug)]
+pub struct AvailabilityPenalty {
+    pub factor: f64,
+    pub horizon_days: i32,
+}
+
+impl AvailabilityPenalty {
+    pub fn is_enabled(&self) -> bool {
+        self.factor < 1.0
+    }
+
+    /// rank of the `filtered_ranks` join, lowered for craftsmen that are booked out
+    pub fn rank(&self) -> SimpleExpr {
+        let rank = Expr::col((filtered_ranks::Entity, filtered_ranks::Column::Rank));
+        if !self.is_enabled() {
+            return rank.into();
+        }
+
+        let horizon = Expr::cust_with_exprs(
+            "now() + make_interval(days => $1)",
+            [Expr::val(self.horizon_days).into()],
+        );
+        let available = available_between(Expr::cust("now()"), horizon);
+
+        rank.mul(Expr::case(available, 1.0).finally(self.factor))
+    }
+}
+
+/// Condition on `profiles` for craftsmen with a slot in the window (`until` being open ended
+/// if missing) that isn't covered by one of their vacations.
+pub fn available(from: NaiveDateTime, until: Option<NaiveDateTime>) -> SimpleExpr {
+    let until = match until {
+        Some(until) => Expr::val(until).into(),
+        None => Expr::cust("'infinity'::timestamp"),
+    };
+
+    available_between(Expr::val(from).into(), until)
+}
+
+fn available_between(from: SimpleExpr, until: SimpleExpr) -> SimpleExpr {
+    // only the part of the slot that lies within the window has to be free
+    Expr::cust_with_exprs(
+        "EXISTS (SELECT 1 FROM availability slot \
+            WHERE slot.profile_id = profiles.id AND slot.kind = 'slot' \
+            AND slot.ends_at > $1 AND slot.starts_at < $2 \
+            AND NOT EXISTS (SELECT 1 FROM availability vacation \
+                WHERE vacation.profile_id = slot.profile_id AND vacation.kind = 'vacation' \
+                AND vacation.starts_at <= GREATEST(slot.starts_at, $1) \
+                AND vacation.ends_at >= LEAST(slot.ends_at, $2)))",
+        [from, until],
+    )
+}
@@ -0,0 +1,216 @@
+use geoutils::Location;
+use serde::{Deserialize, Serialize};
+
+/// media type of GeoJSON responses
+pub const GEOJSON: &str = "application/geo+json";
+
+/// km per degree of latitude, and of longitude at the equator
+const KM_PER_DEGREE: f64 = 6371.0 * std::f64::consts::PI / 180.0;
+
+type Coord = (f64, f64);
+
+/// GeoJSON position, longitude first. An altitude may follow, it is ignored.
+pub type Position = Vec<f64>;
+
+/// closed ring of positions, the first one being repeated at the end
+pub type Ring = Vec<Position>;
+
+/// The areal GeoJSON geometries. The first ring of a polygon is its outline, the others are
+/// holes in it.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum Geometry {
+    Polygon { coordinates: Vec<Ring> },
+    MultiPolygon { coordinates: Vec<Vec<Ring>> },
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(tag = "type")]
+pub struct Point {
+    pub coordinates: [f64; 2],
+}
+
+impl From<&Location> for Point {
+    fn from(loc: &Location) -> Self {
+        Point {
+            coordinates: [loc.longitude(), loc.latitude()],
+        }
+    }
+}
+
+/// geometry of an exported feature
+#[derive(Serialize)]
+#[serde(untagged)]
+pub enum FeatureGeometry<'a> {
+    Point(Point),
+    Area(&'a Geometry),
+}
+
+#[derive(Serialize)]
+#[serde(tag = "type")]
+pub struct Feature<'a, P> {
+    pub geometry: FeatureGeometry<'a>,
+    pub properties: P,
+}
+
+#[derive(Serialize)]
+#[serde(tag = "type")]
+pub struct FeatureCollection<'a, P> {
+    pub features: Vec<Feature<'a, P>>,
+}
+
+impl Geometry {
+    fn polygons(&self) -> impl Iterator<Item = &Vec<Ring>> {
+        match self {
+            Geometry::Polygon { coordinates } => std::slice::from_ref(coordinates).iter(),
+            Geometry::MultiPolygon { coordinates } => coordinates.iter(),
+        }
+    }
+
+    fn rings(&self) -> impl Iterator<Item = &Ring> {
+        self.polygons().flatten()
+    }
+
+    /// edges of all rings, outlines and holes alike
+    fn edges(&self) -> impl Iterator<Item = (Coord, Coord)> + '_ {
+        self.rings()
+            .flat_map(|ring| ring.windows(2))
+            .map(|edge| (coord(&edge[0]), coord(&edge[1])))
+    }
+
+    /// Whether the location lies within the area. Coordinates are treated as planar, which
+    /// is precise enough for areas the size of a region.
+    pub fn contains(&self, loc: &Location) -> bool {
+        self.contains_point((loc.longitude(), loc.latitude()))
+    }
+
+    fn contains_point(&self, point: Coord) -> bool {
+        self.polygons().any(|rings| match rings.split_first() {
+            Some((outline, holes)) => {
+                ring_contains(outline, point)
+                    && !holes.iter().any(|hole| ring_contains(hole, point))
+            }
+            None => false,
+        })
+    }
+
+    /// Whether the areas share any point, i.e. one lies within the other or their outlines cross.
+    pub fn intersects(&self, other: &Geometry) -> bool {
+        let within = |inner: &Geometry, outer: &Geometry| {
+            inner
+                .rings()
+                .filter_map(|ring| ring.first())
+                .any(|position| outer.contains_point(coord(position)))
+        };
+
+        within(self, other)
+            || within(other, self)
+            || self
+                .edges()
+                .any(|edge| other.edges().any(|other| segments_cross(edge, other)))
+    }
+
+    /// Distance in km from the location to the nearest point of the area, 0 within it. Uses
+    /// an equirectangular projection around the location, which is accurate for the distances
+    /// craftsmen drive.
+    pub fn distance_km(&self, loc: &Location) -> f64 {
+        let origin = (loc.longitude(), loc.latitude());
+        if self.contains_point(origin) {
+            return 0.0;
+        }
+
+        let lon_scale = origin.1.to_radians().cos();
+        let project = |(lon, lat): Coord| {
+            (
+                (lon - origin.0) * lon_scale * KM_PER_DEGREE,
+                (lat - origin.1) * KM_PER_DEGREE,
+            )
+        };
+
+        self.edges()
+            .map(|(from, to)| distance_to_segment(project(from), project(to)))
+            .fold(f64::INFINITY, f64::min)
+    }
+
+    /// Checks the structure GeoJSON requires, since serde only checks the types. Returns what's
+    /// wrong with the first broken ring.
+    pub fn check(&self) -> Result<(), &'static str> {
+        let mut polygons = self.polygons().peekable();
+        if polygons.peek().is_none() {
+            return Err("must contain at least one polygon");
+        }
+
+        for rings in polygons {
+            if rings.is_empty() {
+                return Err("polygons must have an outline");
+            }
+
+            for ring in rings {
+                if ring.len() < 4 {
+                    return Err("rings must have at least 4 positions");
+                }
+
+                if ring.first() != ring.last() {
+                    return Err("rings must end with their first position");
+                }
+
+                for position in ring {
+                    let [lon, lat, ..] = position.as_slice() else {
+                        return Err("positions must have a longitude and a latitude");
+                    };
+
+                    if !(-180.0..=180.0).contains(lon) || !(-90.0..=90.0).contains(lat) {
+                        return Err("positions must be valid longitudes and latitudes");
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn coord(position: &Position) -> Coord {
+    (position[0], position[1])
+}
+
+/// even-odd rule, casting a ray from the point towards positive longitudes
+fn ring_contains(ring: &Ring, (x, y): Coord) -> bool {
+    let mut inside = false;
+
+    for edge in ring.windows(2) {
+        let (x1, y1) = (edge[0][0], edge[0][1]);
+        let (x2, y2) = (edge[1][0], edge[1][1]);
+
+        if (y1 > y) != (y2 > y) && x < x1 + (y - y1) * (x2 - x1) / (y2 - y1) {
+            inside = !inside;
+        }
+    }
+
+    inside
+}
+
+/// distance of the origin to the segment between the projected points
+fn distance_to_segment((x1, y1): Coord, (x2, y2): Coord) -> f64 {
+    let (dx, dy) = (x2 - x1, y2 - y1);
+    let length = dx * dx + dy * dy;
+
+    // position of the nearest point along the segment, clamped to its ends
+    let t = if length == 0.0 {
+        0.0
+    } else {
+        (-(x1 * dx + y1 * dy) / length).clamp(0.0, 1.0)
+    };
+
+    (x1 + t * dx).hypot(y1 + t * dy)
+}
+
+fn segments_cross((a, b): (Coord, Coord), (c, d): (Coord, Coord)) -> bool {
+    // which side of the line through the first two points the third one is on
+    let orientation = |p: Coord, q: Coord, r: Coord| {
+        ((q.0 - p.0) * (r.1 - p.1) - (q.1 - p.1) * (r.0 - p.0)).signum()
+    };
+
+    orientation(a, b, c) * orientation(a, b, d) < 0.0
+        && orientation(c, d, a) * orientation(c, d, b) < 0.0
+}
@@ -0,0 +1,121 @@
+use axum::body::Bytes;
+use axum::http::{HeaderMap, StatusCode};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+#[derive(Clone)]
+pub struct StoredResponse {
+    pub status: StatusCode,
+    pub headers: HeaderMap,
+    pub body: Bytes,
+}
+
+struct Entry {
+    fingerprint: u64,
+    created: Instant,
+    // `None` while the first request with this key is still being handled
+    response: Option<StoredResponse>,
+}
+
+pub enum Lookup {
+    /// first time the key is seen, the request has to be handled
+    New,
+    /// the same request is still being handled
+    InFlight,
+    /// the key was used with a different request
+    Mismatch,
+    Replay(StoredResponse),
+}
+
+/// Responses of mutating requests by their `Idempotency-Key`, kept for a fixed window.
+pub struct IdempotencyStore {
+    window: Duration,
+    entries: Mutex<HashMap<String, Entry>>,
+}
+
+pub fn fingerprint(body: &[u8]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    body.hash(&mut hasher);
+    hasher.finish()
+}
+
+impl IdempotencyStore {
+    pub fn new(window: Duration) -> Self {
+        Self {
+            window,
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn begin(&self, key: &str, fingerprint: u64) -> Lookup {
+        let mut entries = self.entries.lock().unwrap();
+        entries.retain(|_, entry| entry.created.elapsed() < self.window);
+
+        match entries.get(key) {
+            Some(entry) if entry.fingerprint != fingerprint => Lookup::Mismatch,
+            Some(Entry {
+                response: Some(response),
+                ..
+            }) => Lookup::Replay(response.clone()),
+            Some(_) => Lookup::InFlight,
+            None => {
+                entries.insert(
+                    key.to_owned(),
+                    Entry {
+                        fingerprint,
+                        created: Instant::now(),
+                        response: None,
+                    },
+                );
+                Lookup::New
+            }
+        }
+    }
+
+    pub fn complete(&self, key: &str, response: StoredResponse) {
+        let mut entries = self.entries.lock().unwrap();
+        if let Some(entry) = entries.get_mut(key) {
+            entry.response = Some(response);
+        }
+    }
+
+    /// forgets the key, so a retry gets handled again
+    pub fn abort(&self, key: &str) {
+        self.entries.lock().unwrap().remove(key);
+    }
+}
+
+/// Key of a request that is being handled. It is forgotten when this is dropped before the
+/// response was stored, so retries of failed, panicked or cancelled requests are handled again
+/// instead of answered with a conflict until the window ends.
+pub struct Pending<'a> {
+    store: &'a IdempotencyStore,
+    key: String,
+    completed: bool,
+}
+
+impl<'a> Pending<'a> {
+    pub fn new(store: &'a IdempotencyStore, key: String) -> Self {
+        Self {
+            store,
+            key,
+            completed: false,
+        }
+    }
+
+    pub fn complete(mut self, response: StoredResponse) {
+        self.store.complete(&self.key, response);
+        self.completed = true;
+    }
+}
+
+impl Drop for Pending<'_> {
+    fn drop(&mut self) {
+        if !self.completed {
+            self.store.abort(&self.key);
+        }
+    }
+}
@@ -0,0 +1,33 @@
+use crate::database::sea_orm_active_enums::{JobStatus, OfferStatus};
+
+/// number of craftsmen a job request is offered to at once
+pub const OFFERS_PER_ROUND: u64 = 5;
+
+impl JobStatus {
+    /// Allowed moves of the job state machine. Offered jobs fall back to open once every
+    /// craftsman declined and nobody is left to offer them to.
+    pub fn can_become(self, next: JobStatus) -> bool {
+        use JobStatus::*;
+
+        matches!(
+            (self, next),
+            (Open, Offered)
+                | (Offered, Open)
+                | (Offered, Accepted)
+                | (Accepted, Completed)
+                | (Open | Offered | Accepted, Cancelled)
+        )
+    }
+}
+
+impl OfferStatus {
+    /// offers can only be answered once, withdrawn ones were never answered
+    pub fn can_become(self, next: OfferStatus) -> bool {
+        use OfferStatus::*;
+
+        matches!(
+            (self, next),
+            (Offered, Accepted) | (Offered, Declined) | (Offered, Withdrawn)
+        )
+    }
+}
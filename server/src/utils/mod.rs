@@ -1,4 +1,13 @@
+pub mod auth;
+pub mod availability;
+pub mod geometry;
+pub mod idempotency;
+pub mod job;
 pub mod postcode_utils;
+pub mod postcode_versions;
 pub mod profile;
 pub mod ranking;
+pub mod result_cache;
+pub mod routing;
 pub mod scoring;
+pub mod validation;
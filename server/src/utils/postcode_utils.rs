@@ -1,41 +1,170 @@
 use geoutils::Location;
+use sea_orm::{ConnectionTrait, DbErr, EntityTrait, QueryOrder};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
 
-use crate::database::sea_orm_active_enums::InGroup;
-use crate::database::{filtered_ranks, postcode};
-use crate::traits::simple_disctance::SimpleDistance;
+use crate::database::{extension_groups, filtered_ranks, postcode};
+use crate::traits::simple_disctance::{DistanceMetric, SimpleDistance};
+
+use super::geometry::Geometry;
 
 use super::ranking::calc_rank;
 
+/// country searches are in if the client doesn't name one
+pub const DEFAULT_COUNTRY: &str = "DE";
+
+/// Postcodes are only unique within a country and kept as strings, since leading zeros
+/// (DE 01067) and letters (UK, NL) are part of them.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PostcodeId {
+    pub country_code: String,
+    pub postcode: String,
+}
+
+impl PostcodeId {
+    /// normalizes user input, codes are stored upper case and without surrounding whitespace
+    pub fn new(country_code: &str, postcode: &str) -> Self {
+        Self {
+            country_code: country_code.trim().to_uppercase(),
+            postcode: postcode.trim().to_uppercase(),
+        }
+    }
+}
+
+impl From<(String, String)> for PostcodeId {
+    fn from((country_code, postcode): (String, String)) -> Self {
+        Self {
+            country_code,
+            postcode,
+        }
+    }
+}
+
+/// Which part of a postcode a craftsman has to reach to cover it. Stored ranks are rebuilt on
+/// startup when it changes.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum CoverageMethod {
+    /// the centroid, with the extension offset of the postcode on top of the radius
+    #[default]
+    Centroid,
+    /// The nearest point of the boundary, service areas have to overlap it. Postcodes without
+    /// a boundary fall back to the centroid.
+    Boundary,
+}
+
+impl FromStr for CoverageMethod {
+    type Err = ();
+
+    fn from_str(method: &str) -> Result<Self, Self::Err> {
+        match method.to_lowercase().as_str() {
+            "centroid" => Ok(CoverageMethod::Centroid),
+            "boundary" => Ok(CoverageMethod::Boundary),
+            _ => Err(()),
+        }
+    }
+}
+
+impl fmt::Display for CoverageMethod {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            CoverageMethod::Centroid => "centroid",
+            CoverageMethod::Boundary => "boundary",
+        })
+    }
+}
+
+impl fmt::Display for PostcodeId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}-{}", self.country_code, self.postcode)
+    }
+}
+
 pub struct Postcode {
-    postcode: i32,
+    id: PostcodeId,
     loc: Location,
     offset: f64,
+    city: Option<String>,
+    extension_group: String,
+    boundary: Option<Geometry>,
 }
 
-impl InGroup {
-    pub fn get_offset_km(&self) -> f64 {
-        match self {
-            InGroup::GroupA => 0.0,
-            InGroup::GroupB => 2.0,
-            InGroup::GroupC => 5.0,
-        }
-    }
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PostcodeInfo {
+    #[serde(flatten)]
+    pub id: PostcodeId,
+    pub city: Option<String>,
+    pub lat: f64,
+    pub lon: f64,
 }
 
-impl Into<Postcode> for postcode::Model {
-    fn into(self) -> Postcode {
-        let Self {
+impl Postcode {
+    /// the offset of the postcode is the one of its extension group unless it has its own
+    pub fn new(postcode: postcode::Model, group_offsets: &HashMap<String, f64>) -> Self {
+        let postcode::Model {
+            country_code,
             postcode,
             lat,
             lon,
             postcode_extension_distance_group,
+            extension_offset_km,
+            city,
+            boundary,
             ..
-        } = self;
+        } = postcode;
+
+        let offset = extension_offset_km
+            .or_else(|| {
+                group_offsets
+                    .get(&postcode_extension_distance_group)
+                    .copied()
+            })
+            .unwrap_or_default();
 
         Postcode {
-            postcode,
+            id: PostcodeId {
+                country_code,
+                postcode,
+            },
             loc: Location::new(lat, lon),
-            offset: postcode_extension_distance_group.get_offset_km(),
+            offset,
+            city,
+            extension_group: postcode_extension_distance_group,
+            // checked before it was stored
+            boundary: boundary.and_then(|boundary| serde_json::from_value(boundary).ok()),
+        }
+    }
+}
+
+/// all postcodes, ordered by country and code, with the current offsets of their extension groups
+pub async fn load_postcodes<C: ConnectionTrait>(db: &C) -> Result<Vec<Postcode>, DbErr> {
+    let group_offsets: HashMap<String, f64> = extension_groups::Entity::find()
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|group| (group.name, group.offset_km))
+        .collect();
+
+    let postcodes = postcode::Entity::find()
+        .order_by_asc(postcode::Column::CountryCode)
+        .order_by_asc(postcode::Column::Postcode)
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|postcode| Postcode::new(postcode, &group_offsets))
+        .collect();
+
+    Ok(postcodes)
+}
+
+impl filtered_ranks::Model {
+    pub fn postcode_id(&self) -> PostcodeId {
+        PostcodeId {
+            country_code: self.country_code.clone(),
+            postcode: self.postcode.clone(),
         }
     }
 }
@@ -46,29 +175,103 @@ pub struct PatchFilters {
     pub max_driving_distance: f64,
     pub profile_score: f64,
     pub loc: Location,
+    pub metric: DistanceMetric,
+    pub coverage: CoverageMethod,
+    /// replaces the radius if present
+    pub service_area: Option<Geometry>,
 }
 
 impl Postcode {
+    pub fn id(&self) -> &PostcodeId {
+        &self.id
+    }
+
+    /// offset in km granted on top of every radius
+    pub fn offset(&self) -> f64 {
+        self.offset
+    }
+
+    pub fn location(&self) -> &Location {
+        &self.loc
+    }
+
+    pub fn extension_group(&self) -> &str {
+        &self.extension_group
+    }
+
+    pub fn boundary(&self) -> Option<&Geometry> {
+        self.boundary.as_ref()
+    }
+
+    pub fn info(&self) -> PostcodeInfo {
+        PostcodeInfo {
+            id: self.id.clone(),
+            city: self.city.clone(),
+            lat: self.loc.latitude(),
+            lon: self.loc.longitude(),
+        }
+    }
+
+    /// whether ranks computed for the other postcode are still valid for this one
+    pub fn same_location(&self, other: &Postcode) -> bool {
+        self.loc.latitude() == other.loc.latitude()
+            && self.loc.longitude() == other.loc.longitude()
+            && self.offset == other.offset
+            && self.boundary == other.boundary
+    }
+
     pub fn get_model_opt(&self, patch: &PatchFilters) -> Option<filtered_ranks::Model> {
+        let dist: f64 = self.loc.calculate_distance_km(&patch.loc, patch.metric);
+
+        self.get_model_with_distance(patch, dist)
+    }
+
+    /// like `get_model_opt`, for a distance that was measured some other way, e.g. on roads
+    pub fn get_model_with_distance(
+        &self,
+        patch: &PatchFilters,
+        dist: f64,
+    ) -> Option<filtered_ranks::Model> {
         let Self {
-            postcode,
-            loc,
-            offset,
+            id, loc, offset, ..
         } = self;
 
-        let dist: f64 = loc.calculate_simple_distance_km(&patch.loc);
+        let boundary = match patch.coverage {
+            CoverageMethod::Centroid => None,
+            CoverageMethod::Boundary => self.boundary.as_ref(),
+        };
 
-        if dist > patch.max_driving_distance + offset {
-            return None;
-        }
+        let required_distance = match (&patch.service_area, boundary) {
+            // postcodes within the area are covered no matter how far away they are
+            (Some(area), Some(boundary)) if area.intersects(boundary) => 0.0,
+            (Some(area), None) if area.contains(loc) => 0.0,
+            (Some(_), _) => return None,
+            (None, boundary) => {
+                let required_distance = match boundary {
+                    // the boundary is what the extension offset approximates, so it isn't
+                    // granted on top
+                    Some(boundary) => boundary.distance_km(&patch.loc),
+                    // the extension offset of the postcode is granted on top of every radius
+                    None => (dist - offset).max(0.0),
+                };
+
+                if required_distance > patch.max_driving_distance {
+                    return None;
+                }
+
+                required_distance
+            }
+        };
 
         let rank = calc_rank(dist, patch.profile_score);
 
         Some(filtered_ranks::Model {
             profile_id: patch.profile_id,
-            postcode: *postcode,
+            country_code: id.country_code.clone(),
+            postcode: id.postcode.clone(),
             distance: dist,
-            rank: rank,
+            rank,
+            required_distance,
         })
     }
 }
@@ -0,0 +1,56 @@
+use axum::headers::ETag;
+use std::collections::HashMap;
+use std::sync::RwLock;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use super::postcode_utils::PostcodeId;
+
+/// Version counter per postcode, bumped whenever a profile serving that postcode changes.
+/// The counters live in memory only, so the startup time is mixed into every ETag to keep
+/// tags from a previous run from matching after a restart.
+pub struct PostcodeVersions {
+    epoch: u64,
+    versions: RwLock<HashMap<PostcodeId, u64>>,
+}
+
+impl Default for PostcodeVersions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PostcodeVersions {
+    pub fn new() -> Self {
+        let epoch = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|time| time.as_secs())
+            .unwrap_or_default();
+
+        Self {
+            epoch,
+            versions: RwLock::new(HashMap::new()),
+        }
+    }
+
+    pub fn get(&self, postcode: &PostcodeId) -> u64 {
+        let versions = self.versions.read().unwrap();
+        versions.get(postcode).copied().unwrap_or_default()
+    }
+
+    pub fn bump<I: IntoIterator<Item = PostcodeId>>(&self, postcodes: I) {
+        let mut versions = self.versions.write().unwrap();
+        for postcode in postcodes {
+            *versions.entry(postcode).or_default() += 1;
+        }
+    }
+
+    /// strong ETag for one page of search results that don't depend on the current time
+    pub fn etag(&self, postcode: &PostcodeId, version: u64, offset: u64) -> Option<ETag> {
+        // spaces (UK postcodes) aren't allowed in entity tags
+        let postcode = postcode.to_string().replace(char::is_whitespace, "_");
+
+        format!("\"{:x}-{postcode}-{version}-{offset}\"", self.epoch)
+            .parse()
+            .ok()
+    }
+}
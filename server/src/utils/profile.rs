@@ -1,11 +1,13 @@
+use axum::headers::ETag;
 use sea_orm::FromQueryResult;
 
 use serde::Serialize;
 
 use crate::database::profiles;
 use crate::rest::patch_craftsmen;
+use crate::utils::geometry::{Feature, FeatureGeometry, Point};
 
-#[derive(Serialize)]
+#[derive(Clone, Serialize)]
 pub struct Craftsman {
     id: i32,
     name: String,
@@ -14,6 +16,67 @@ pub struct Craftsman {
     street: String,
     house_number: String,
     distance: f64,
+    lat: f64,
+    lon: f64,
+}
+
+/// properties of a craftsman exported as GeoJSON feature
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CraftsmanProperties {
+    id: i32,
+    name: String,
+    ranking_score: f64,
+    street: String,
+    house_number: String,
+    distance: f64,
+}
+
+impl Craftsman {
+    pub fn into_feature(self) -> Feature<'static, CraftsmanProperties> {
+        let Self {
+            id,
+            name,
+            ranking_score,
+            street,
+            house_number,
+            distance,
+            lat,
+            lon,
+        } = self;
+
+        Feature {
+            geometry: FeatureGeometry::Point(Point {
+                coordinates: [lon, lat],
+            }),
+            properties: CraftsmanProperties {
+                id,
+                name,
+                ranking_score,
+                street,
+                house_number,
+                distance,
+            },
+        }
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CraftsmanDetails {
+    id: i32,
+    name: String,
+    city: String,
+    street: String,
+    house_number: String,
+    max_driving_distance: f64,
+    profile_score: f64,
+    profile_picture_score: f64,
+    profile_description_score: f64,
+    review_score: Option<f64>,
+    /// km to the postcode the craftsman was looked up for, if they are ranked for it
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub distance: Option<f64>,
 }
 
 #[derive(FromQueryResult, Serialize)]
@@ -23,6 +86,8 @@ pub struct ProfileWithRank {
     last_name: String,
     street: String,
     house_number: String,
+    lon: f64,
+    lat: f64,
     distance: f64,
     rank: f64,
 }
@@ -36,6 +101,8 @@ impl Into<Craftsman> for ProfileWithRank {
             rank,
             street,
             house_number,
+            lon,
+            lat,
             distance,
             ..
         } = self;
@@ -47,6 +114,8 @@ impl Into<Craftsman> for ProfileWithRank {
             house_number,
             ranking_score: rank,
             distance,
+            lat,
+            lon,
         }
     }
 }
@@ -70,3 +139,43 @@ impl Into<patch_craftsmen::QueryResult> for profiles::Model {
         patch_craftsmen::QueryResult { id, updated }
     }
 }
+
+impl From<profiles::Model> for CraftsmanDetails {
+    fn from(profile: profiles::Model) -> Self {
+        let profiles::Model {
+            id,
+            first_name,
+            last_name,
+            city,
+            street,
+            house_number,
+            max_driving_distance,
+            profile_score,
+            profile_picture_score,
+            profile_description_score,
+            review_score,
+            ..
+        } = profile;
+
+        CraftsmanDetails {
+            id,
+            name: format!("{first_name} {last_name}"),
+            city,
+            street,
+            house_number,
+            max_driving_distance,
+            profile_score,
+            profile_picture_score,
+            profile_description_score,
+            review_score,
+            distance: None,
+        }
+    }
+}
+
+impl profiles::Model {
+    /// strong ETag of the stored profile, changes with every successful update
+    pub fn etag(&self) -> Option<ETag> {
+        format!("\"{}-{}\"", self.id, self.version).parse().ok()
+    }
+}
@@ -0,0 +1,129 @@
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use super::postcode_utils::PostcodeId;
+use super::profile::Craftsman;
+
+struct Entry {
+    craftsmen: Arc<Vec<Craftsman>>,
+    version: u64,
+    inserted: Instant,
+    last_used: u64,
+}
+
+struct Entries {
+    map: HashMap<PostcodeId, Entry>,
+    clock: u64,
+}
+
+/// Bounded LRU cache of the top ranked craftsmen per postcode.
+/// Entries are tagged with the postcode version they were read at, so a lookup never returns
+/// rows that were computed before the latest change to that postcode.
+pub struct ResultCache {
+    capacity: usize,
+    ttl: Duration,
+    entries: Mutex<Entries>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+#[derive(Serialize)]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    pub entries: usize,
+    pub capacity: usize,
+}
+
+impl ResultCache {
+    pub fn new(capacity: usize, ttl: Duration) -> Self {
+        Self {
+            capacity,
+            ttl,
+            entries: Mutex::new(Entries {
+                map: HashMap::new(),
+                clock: 0,
+            }),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        }
+    }
+
+    pub fn get(&self, postcode: &PostcodeId, version: u64) -> Option<Arc<Vec<Craftsman>>> {
+        let mut entries = self.entries.lock().unwrap();
+        entries.clock += 1;
+        let clock = entries.clock;
+
+        let result = match entries.map.get_mut(postcode) {
+            Some(entry) if entry.version == version && entry.inserted.elapsed() < self.ttl => {
+                entry.last_used = clock;
+                Some(entry.craftsmen.clone())
+            }
+            Some(_) => {
+                entries.map.remove(postcode);
+                None
+            }
+            None => None,
+        };
+
+        match result {
+            Some(_) => self.hits.fetch_add(1, Ordering::Relaxed),
+            None => self.misses.fetch_add(1, Ordering::Relaxed),
+        };
+
+        result
+    }
+
+    pub fn insert(&self, postcode: PostcodeId, version: u64, craftsmen: Arc<Vec<Craftsman>>) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        let mut entries = self.entries.lock().unwrap();
+        entries.clock += 1;
+        let clock = entries.clock;
+
+        if !entries.map.contains_key(&postcode) && entries.map.len() >= self.capacity {
+            let lru = entries
+                .map
+                .iter()
+                .min_by_key(|(_, entry)| entry.last_used)
+                .map(|(postcode, _)| postcode.clone());
+
+            if let Some(lru) = lru {
+                entries.map.remove(&lru);
+            }
+        }
+
+        entries.map.insert(
+            postcode,
+            Entry {
+                craftsmen,
+                version,
+                inserted: Instant::now(),
+                last_used: clock,
+            },
+        );
+    }
+
+    pub fn invalidate<'a, I: IntoIterator<Item = &'a PostcodeId>>(&self, postcodes: I) {
+        let mut entries = self.entries.lock().unwrap();
+        for postcode in postcodes {
+            entries.map.remove(postcode);
+        }
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        let entries = self.entries.lock().unwrap();
+
+        CacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            entries: entries.map.len(),
+            capacity: self.capacity,
+        }
+    }
+}
@@ -0,0 +1,426 @@
+use axum::async_trait;
+use geoutils::Location;
+use hyper::client::HttpConnector;
+use hyper::{Client, Uri};
+use sea_orm::{ColumnTrait, ConnectionTrait, DbErr, EntityTrait, QueryFilter};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::time::Instant;
+
+use crate::database::{profiles, road_distances};
+use crate::traits::simple_disctance::DistanceMetric;
+
+use super::postcode_utils::{CoverageMethod, Postcode, PostcodeId};
+
+/// destinations per request, keeps the URLs of table requests reasonably short
+const ROUTING_CHUNK_SIZE: usize = 100;
+
+#[derive(Debug)]
+pub enum RoutingError {
+    Request,
+    Timeout,
+    /// the backend answered, but not with distances
+    Response,
+}
+
+#[async_trait]
+pub trait RoutingProvider: Send + Sync {
+    /// driving distances in km from `from` to every location in `to`, `None` where there is no
+    /// route
+    async fn distances_km(
+        &self,
+        from: &Location,
+        to: &[&Location],
+    ) -> Result<Vec<Option<f64>>, RoutingError>;
+}
+
+/// Client for the table service of OSRM, or anything that speaks its API. Plain HTTP only,
+/// the backend is expected to run next to the server.
+pub struct OsrmClient {
+    base_url: String,
+    profile: String,
+    timeout: Duration,
+    client: Client<HttpConnector>,
+}
+
+#[derive(Deserialize)]
+struct TableResponse {
+    code: String,
+    /// in meters, one row per source
+    distances: Option<Vec<Vec<Option<f64>>>>,
+}
+
+impl OsrmClient {
+    pub fn new(base_url: &str, profile: &str, timeout: Duration) -> Self {
+        Self {
+            base_url: base_url.trim_end_matches('/').to_owned(),
+            profile: profile.to_owned(),
+            timeout,
+            client: Client::new(),
+        }
+    }
+}
+
+#[async_trait]
+impl RoutingProvider for OsrmClient {
+    async fn distances_km(
+        &self,
+        from: &Location,
+        to: &[&Location],
+    ) -> Result<Vec<Option<f64>>, RoutingError> {
+        if to.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        // OSRM wants longitude first
+        let coordinates = std::iter::once(from)
+            .chain(to.iter().copied())
+            .map(|loc| format!("{:.6},{:.6}", loc.longitude(), loc.latitude()))
+            .collect::<Vec<_>>()
+            .join(";");
+        let destinations = (1..=to.len())
+            .map(|index| index.to_string())
+            .collect::<Vec<_>>()
+            .join(";");
+
+        let uri: Uri = format!(
+            "{}/table/v1/{}/{coordinates}?sources=0&destinations={destinations}&annotations=distance",
+            self.base_url, self.profile
+        )
+        .parse()
+        .map_err(|_| RoutingError::Request)?;
+
+        let response = tokio::time::timeout(self.timeout, self.client.get(uri))
+            .await
+            .map_err(|_| RoutingError::Timeout)?
+            .map_err(|_| RoutingError::Request)?;
+
+        if !response.status().is_success() {
+            return Err(RoutingError::Response);
+        }
+
+        let body = hyper::body::to_bytes(response.into_body())
+            .await
+            .map_err(|_| RoutingError::Request)?;
+        let table: TableResponse =
+            serde_json::from_slice(&body).map_err(|_| RoutingError::Response)?;
+
+        match table.distances {
+            Some(mut rows)
+                if table.code == "Ok" && rows.len() == 1 && rows[0].len() == to.len() =>
+            {
+                let distances = rows.remove(0);
+                Ok(distances
+                    .into_iter()
+                    .map(|distance| distance.map(|meters| meters / 1000.0))
+                    .collect())
+            }
+            _ => Err(RoutingError::Response),
+        }
+    }
+}
+
+/// How distances between craftsmen and postcodes are measured when materializing ranks:
+/// as the crow flies, or along roads if a routing backend is configured.
+pub struct Distances {
+    pub metric: DistanceMetric,
+    /// the coverage method the ranks are materialized with
+    pub coverage: CoverageMethod,
+    pub routing: Option<Arc<dyn RoutingProvider>>,
+    /// Total time one computation of ranks may wait for the backend. Ranks are computed
+    /// while profiles are locked, so a slow backend must not hold them for long.
+    pub routing_budget: Duration,
+}
+
+impl Distances {
+    /// when computing ranks that start now has to stop waiting for the backend
+    pub fn routing_deadline(&self) -> Instant {
+        Instant::now() + self.routing_budget
+    }
+
+    /// Driving distances from the profile to the postcodes, cached in `road_distances` as long
+    /// as the profile doesn't move. Postcodes the backend couldn't be asked for before the
+    /// deadline are missing from the result, so callers fall back to the straight line
+    /// distance for them.
+    pub async fn driving_distances<C: ConnectionTrait>(
+        &self,
+        profile: &profiles::Model,
+        postcodes: &[&Postcode],
+        deadline: Instant,
+        txn: &C,
+    ) -> Result<HashMap<PostcodeId, Option<f64>>, DbErr> {
+        let Some(routing) = &self.routing else {
+            return Ok(HashMap::new());
+        };
+
+        let mut distances: HashMap<PostcodeId, Option<f64>> = road_distances::Entity::find()
+            .filter(road_distances::Column::ProfileId.eq(profile.id))
+            .filter(road_distances::Column::Lat.eq(profile.lat))
+            .filter(road_distances::Column::Lon.eq(profile.lon))
+            .all(txn)
+            .await?
+            .into_iter()
+            .map(|cached| {
+                let distance = cached.distance_km;
+                (
+                    PostcodeId::from((cached.country_code, cached.postcode)),
+                    distance,
+                )
+            })
+            .collect();
+
+        let misses: Vec<&Postcode> = postcodes
+            .iter()
+            .filter(|postcode| !distances.contains_key(postcode.id()))
+            .copied()
+            .collect();
+
+        let origin = Location::new(profile.lat, profile.lon);
+        let fetched = fetch_distances(routing.as_ref(), &origin, &misses, deadline).await;
+
+        for chunk in fetched.chunks(ROUTING_CHUNK_SIZE) {
+            let rows = chunk.iter().map(|(id, distance)| {
+                road_distances::ActiveModel::from(road_distances::Model {
+                    profile_id: profile.id,
+                    country_code: id.country_code.clone(),
+                    postcode: id.postcode.clone(),
+                    lat: profile.lat,
+                    lon: profile.lon,
+                    distance_km: *distance,
+                })
+            });
+
+            road_distances::Entity::insert_many(rows)
+                .on_conflict(
+                    sea_query::OnConflict::columns([
+                        road_distances::Column::ProfileId,
+                        road_distances::Column::CountryCode,
+                        road_distances::Column::Postcode,
+                    ])
+                    .update_columns([
+                        road_distances::Column::Lat,
+                        road_distances::Column::Lon,
+                        road_distances::Column::DistanceKm,
+                    ])
+                    .to_owned(),
+                )
+                .exec(txn)
+                .await?;
+        }
+
+        distances.extend(fetched);
+        Ok(distances)
+    }
+}
+
+/// Asks the backend for the distances in chunks until the deadline. Postcodes of chunks it
+/// failed for or didn't get to are left out, so they are neither cached nor used.
+async fn fetch_distances(
+    routing: &dyn RoutingProvider,
+    origin: &Location,
+    postcodes: &[&Postcode],
+    deadline: Instant,
+) -> Vec<(PostcodeId, Option<f64>)> {
+    let mut fetched = Vec::new();
+
+    for chunk in postcodes.chunks(ROUTING_CHUNK_SIZE) {
+        let locations: Vec<&Location> = chunk.iter().map(|postcode| postcode.location()).collect();
+
+        let Ok(answer) =
+            tokio::time::timeout_at(deadline, routing.distances_km(origin, &locations)).await
+        else {
+            break;
+        };
+
+        // the backend being down shouldn't block profile updates
+        let Ok(distances) = answer else {
+            continue;
+        };
+
+        fetched.extend(
+            chunk
+                .iter()
+                .map(|postcode| postcode.id().clone())
+                .zip(distances),
+        );
+    }
+
+    fetched
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::postcode;
+    use axum::Router;
+    use std::net::TcpListener;
+    use std::sync::Mutex;
+
+    const TIMEOUT: Duration = Duration::from_secs(5);
+
+    /// Answers every request with `body` after `delay`. Returns the base URL and the URIs
+    /// requested so far.
+    fn stub(body: &str, delay: Duration) -> (String, Arc<Mutex<Vec<Uri>>>) {
+        stub_sequence(vec![body.to_owned()], delay)
+    }
+
+    /// Answers the n-th request with the n-th body, and all later ones with the last body.
+    fn stub_sequence(bodies: Vec<String>, delay: Duration) -> (String, Arc<Mutex<Vec<Uri>>>) {
+        let requested = Arc::new(Mutex::new(Vec::new()));
+        let router = Router::new().fallback({
+            let requested = requested.clone();
+            move |uri: Uri| async move {
+                let call = {
+                    let mut requested = requested.lock().unwrap();
+                    requested.push(uri);
+                    requested.len() - 1
+                };
+                tokio::time::sleep(delay).await;
+                bodies[call.min(bodies.len() - 1)].clone()
+            }
+        });
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let server = axum::Server::from_tcp(listener)
+            .unwrap()
+            .serve(router.into_make_service());
+        tokio::spawn(server);
+
+        (url, requested)
+    }
+
+    fn postcode(code: &str, lat: f64, lon: f64) -> Postcode {
+        let model = postcode::Model {
+            country_code: "DE".to_owned(),
+            postcode: code.to_owned(),
+            lon,
+            lat,
+            postcode_extension_distance_group: "group_a".to_owned(),
+            extension_offset_km: None,
+            city: None,
+            boundary: None,
+            created_at: None,
+            updated_at: None,
+        };
+        Postcode::new(model, &HashMap::new())
+    }
+
+    #[tokio::test]
+    async fn asks_for_lon_lat_and_converts_to_km() {
+        let (url, requested) = stub(
+            r#"{"code":"Ok","distances":[[1500.0,null]]}"#,
+            Duration::ZERO,
+        );
+        let client = OsrmClient::new(&url, "driving", TIMEOUT);
+
+        let from = Location::new(52.5, 13.4);
+        let (near, unreachable) = (Location::new(52.6, 13.3), Location::new(54.1, 7.9));
+        let distances = client
+            .distances_km(&from, &[&near, &unreachable])
+            .await
+            .unwrap();
+
+        assert_eq!(distances, vec![Some(1.5), None]);
+
+        let requested = requested.lock().unwrap();
+        assert_eq!(
+            requested[0].path(),
+            "/table/v1/driving/13.400000,52.500000;13.300000,52.600000;7.900000,54.100000"
+        );
+        assert_eq!(
+            requested[0].query(),
+            Some("sources=0&destinations=1;2&annotations=distance")
+        );
+    }
+
+    #[tokio::test]
+    async fn rejects_failed_answers() {
+        let from = Location::new(52.5, 13.4);
+        let to = [Location::new(52.6, 13.3), Location::new(54.1, 7.9)];
+        let to: Vec<&Location> = to.iter().collect();
+
+        for body in [
+            r#"{"code":"NoSegment","message":"Could not find a matching segment"}"#,
+            r#"{"code":"NoTable","distances":[[1500.0,2500.0]]}"#,
+            // fewer distances than destinations
+            r#"{"code":"Ok","distances":[[1500.0]]}"#,
+            // more than the one source asked for
+            r#"{"code":"Ok","distances":[[1500.0,2500.0],[0.0,0.0]]}"#,
+        ] {
+            let (url, _) = stub(body, Duration::ZERO);
+            let client = OsrmClient::new(&url, "driving", TIMEOUT);
+
+            let result = client.distances_km(&from, &to).await;
+            assert!(matches!(result, Err(RoutingError::Response)), "{body}");
+        }
+    }
+
+    #[tokio::test]
+    async fn times_out() {
+        let (url, _) = stub(
+            r#"{"code":"Ok","distances":[[1500.0]]}"#,
+            Duration::from_secs(2),
+        );
+        let client = OsrmClient::new(&url, "driving", Duration::from_millis(50));
+
+        let result = client
+            .distances_km(&Location::new(52.5, 13.4), &[&Location::new(52.6, 13.3)])
+            .await;
+        assert!(matches!(result, Err(RoutingError::Timeout)));
+    }
+
+    #[tokio::test]
+    async fn failed_chunks_are_left_out() {
+        // the first chunk is answered, the second one fails
+        let answered = vec!["1000.0"; ROUTING_CHUNK_SIZE].join(",");
+        let (url, requested) = stub_sequence(
+            vec![
+                format!(r#"{{"code":"Ok","distances":[[{answered}]]}}"#),
+                r#"{"code":"NoTable"}"#.to_owned(),
+            ],
+            Duration::ZERO,
+        );
+        let client = OsrmClient::new(&url, "driving", TIMEOUT);
+
+        let postcodes: Vec<Postcode> = (0..ROUTING_CHUNK_SIZE + 10)
+            .map(|index| postcode(&format!("{:05}", 10000 + index), 52.5, 13.4))
+            .collect();
+        let postcodes: Vec<&Postcode> = postcodes.iter().collect();
+
+        let deadline = Instant::now() + TIMEOUT;
+        let fetched =
+            fetch_distances(&client, &Location::new(52.5, 13.4), &postcodes, deadline).await;
+
+        assert_eq!(requested.lock().unwrap().len(), 2);
+        // callers use the straight line for the missing ones, and only cache what is returned
+        assert_eq!(fetched.len(), ROUTING_CHUNK_SIZE);
+        assert!(fetched
+            .iter()
+            .zip(&postcodes)
+            .all(|((id, distance), postcode)| id == postcode.id() && *distance == Some(1.0)));
+    }
+
+    #[tokio::test]
+    async fn stops_at_the_deadline() {
+        let (url, requested) = stub(r#"{"code":"Ok","distances":[[1000.0]]}"#, TIMEOUT);
+        let client = OsrmClient::new(&url, "driving", TIMEOUT);
+
+        let postcodes: Vec<Postcode> = (0..3 * ROUTING_CHUNK_SIZE)
+            .map(|index| postcode(&format!("{:05}", 10000 + index), 52.5, 13.4))
+            .collect();
+        let postcodes: Vec<&Postcode> = postcodes.iter().collect();
+
+        let started = Instant::now();
+        let deadline = started + Duration::from_millis(100);
+        let fetched =
+            fetch_distances(&client, &Location::new(52.5, 13.4), &postcodes, deadline).await;
+
+        assert!(fetched.is_empty());
+        assert!(started.elapsed() < TIMEOUT);
+        // the remaining chunks aren't asked for after the deadline
+        assert_eq!(requested.lock().unwrap().len(), 1);
+    }
+}
@@ -1,5 +1,22 @@
-pub fn calc_score(pic_score: f64, desc_score: f64) -> f64 {
-    0.4 * pic_score + 0.6 * desc_score
+/// share of the customer reviews in the profile score, once a craftsman has any
+const REVIEW_WEIGHT: f64 = 0.3;
+
+/// mean rating every craftsman starts out with
+const PRIOR_RATING: f64 = 3.5;
+
+/// number of reviews the prior counts for, so a single 5 star review doesn't put anyone on top
+const PRIOR_WEIGHT: f64 = 5.0;
+
+const MIN_RATING: f64 = 1.0;
+const MAX_RATING: f64 = 5.0;
+
+pub fn calc_score(pic_score: f64, desc_score: f64, review_score: Option<f64>) -> f64 {
+    let profile_score = 0.4 * pic_score + 0.6 * desc_score;
+
+    match review_score {
+        Some(review_score) => (1.0 - REVIEW_WEIGHT) * profile_score + REVIEW_WEIGHT * review_score,
+        None => profile_score,
+    }
 }
 
 pub fn calc_score_from_options(
@@ -7,11 +24,28 @@ pub fn calc_score_from_options(
     desc_score: Option<f64>,
     pic_score_old: f64,
     desc_score_old: f64,
+    review_score: Option<f64>,
 ) -> Option<f64> {
     match (pic_score, desc_score) {
         (None, None) => None,
-        (None, Some(desc_score)) => Some(calc_score(pic_score_old, desc_score)),
-        (Some(pic_score), None) => Some(calc_score(pic_score, desc_score_old)),
-        (Some(pic_score), Some(desc_score)) => Some(calc_score(pic_score, desc_score)),
+        (None, Some(desc_score)) => Some(calc_score(pic_score_old, desc_score, review_score)),
+        (Some(pic_score), None) => Some(calc_score(pic_score, desc_score_old, review_score)),
+        (Some(pic_score), Some(desc_score)) => {
+            Some(calc_score(pic_score, desc_score, review_score))
+        }
     }
 }
+
+/// Bayesian average of the approved ratings, shrunk towards the prior for few reviews.
+pub fn bayesian_rating(count: i64, sum: i64) -> Option<f64> {
+    if count == 0 {
+        return None;
+    }
+
+    Some((PRIOR_WEIGHT * PRIOR_RATING + sum as f64) / (PRIOR_WEIGHT + count as f64))
+}
+
+/// maps a rating onto [0, 1], the range of the other scores
+pub fn review_score(rating: f64) -> f64 {
+    (rating - MIN_RATING) / (MAX_RATING - MIN_RATING)
+}
@@ -0,0 +1,163 @@
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::Serialize;
+
+use crate::rest::app_state::AppState;
+use crate::utils::geometry::Geometry;
+use crate::utils::postcode_utils::PostcodeId;
+
+/// upper bound for `maxDrivingDistance` in meters
+pub const MAX_DRIVING_DISTANCE: f64 = 500_000.0;
+
+/// upper bound for profile descriptions in characters
+pub const MAX_DESCRIPTION_LENGTH: usize = 2000;
+
+pub type Rule<T> = fn(&T, &AppState) -> Result<(), &'static str>;
+
+pub trait Validate {
+    fn validate(&self, state: &AppState) -> Result<(), ValidationErrors>;
+}
+
+#[derive(Serialize)]
+pub struct FieldError {
+    /// empty if the error is about the body as a whole
+    pub field: &'static str,
+    pub message: &'static str,
+}
+
+#[derive(Serialize, Default)]
+pub struct ValidationErrors {
+    pub errors: Vec<FieldError>,
+}
+
+impl IntoResponse for ValidationErrors {
+    fn into_response(self) -> Response {
+        (StatusCode::UNPROCESSABLE_ENTITY, Json(self)).into_response()
+    }
+}
+
+/// Collects the errors of all fields, so clients get to see everything that's wrong at once.
+pub struct Validator<'a> {
+    state: &'a AppState,
+    errors: ValidationErrors,
+}
+
+impl<'a> Validator<'a> {
+    pub fn new(state: &'a AppState) -> Self {
+        Self {
+            state,
+            errors: ValidationErrors::default(),
+        }
+    }
+
+    pub fn field<T: ?Sized>(mut self, field: &'static str, value: &T, rules: &[Rule<T>]) -> Self {
+        // only the first broken rule is reported per field
+        if let Some(message) = rules.iter().find_map(|rule| rule(value, self.state).err()) {
+            self.errors.errors.push(FieldError { field, message });
+        }
+        self
+    }
+
+    /// same as `field`, but for optional values that are only checked if present
+    pub fn optional<T>(self, field: &'static str, value: &Option<T>, rules: &[Rule<T>]) -> Self {
+        match value {
+            Some(value) => self.field(field, value, rules),
+            None => self,
+        }
+    }
+
+    /// for constraints between fields, reported on the given one
+    pub fn check(mut self, field: &'static str, valid: bool, message: &'static str) -> Self {
+        if !valid {
+            self.errors.errors.push(FieldError { field, message });
+        }
+        self
+    }
+
+    pub fn finish(self) -> Result<(), ValidationErrors> {
+        if self.errors.errors.is_empty() {
+            Ok(())
+        } else {
+            Err(self.errors)
+        }
+    }
+}
+
+pub fn finite(value: &f64, _: &AppState) -> Result<(), &'static str> {
+    if value.is_finite() {
+        Ok(())
+    } else {
+        Err("must be a finite number")
+    }
+}
+
+pub fn score(value: &f64, _: &AppState) -> Result<(), &'static str> {
+    if (0.0..=1.0).contains(value) {
+        Ok(())
+    } else {
+        Err("must be between 0 and 1")
+    }
+}
+
+pub fn positive(value: &f64, _: &AppState) -> Result<(), &'static str> {
+    if *value > 0.0 {
+        Ok(())
+    } else {
+        Err("must be positive")
+    }
+}
+
+pub fn non_negative(value: &f64, _: &AppState) -> Result<(), &'static str> {
+    if *value >= 0.0 {
+        Ok(())
+    } else {
+        Err("must not be negative")
+    }
+}
+
+pub fn driving_distance(value: &f64, _: &AppState) -> Result<(), &'static str> {
+    if *value <= MAX_DRIVING_DISTANCE {
+        Ok(())
+    } else {
+        Err("must be at most 500000 meters")
+    }
+}
+
+pub fn known_postcode(value: &PostcodeId, state: &AppState) -> Result<(), &'static str> {
+    if state.postcodes().iter().any(|known| known.id() == value) {
+        Ok(())
+    } else {
+        Err("unknown postcode")
+    }
+}
+
+pub fn not_blank(value: &str, _: &AppState) -> Result<(), &'static str> {
+    if value.trim().is_empty() {
+        Err("must not be blank")
+    } else {
+        Ok(())
+    }
+}
+
+pub fn description_length(value: &str, _: &AppState) -> Result<(), &'static str> {
+    if value.chars().count() <= MAX_DESCRIPTION_LENGTH {
+        Ok(())
+    } else {
+        Err("must be at most 2000 characters")
+    }
+}
+
+pub fn rating(value: &i16, _: &AppState) -> Result<(), &'static str> {
+    if (1..=5).contains(value) {
+        Ok(())
+    } else {
+        Err("must be between 1 and 5")
+    }
+}
+
+pub fn geometry(value: &Geometry, _: &AppState) -> Result<(), &'static str> {
+    value.check()
+}